@@ -0,0 +1,63 @@
+use std::{
+    fs::read_to_string,
+    path::Path,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn main() {
+    // HEAD only changes when switching branches, new commits update the branch ref instead
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Some(head_ref) = read_to_string(".git/HEAD")
+        .ok()
+        .and_then(|head| Some(head.strip_prefix("ref: ")?.trim().to_string()))
+    {
+        println!("cargo:rerun-if-changed=.git/{head_ref}");
+    }
+    if Path::new(".git/packed-refs").exists() {
+        println!("cargo:rerun-if-changed=.git/packed-refs");
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ESPUP_GIT_COMMIT={git_commit}");
+
+    // Honor reproducible builds: https://reproducible-builds.org/docs/source-date-epoch/
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        });
+    println!("cargo:rustc-env=ESPUP_BUILD_DATE={}", date(timestamp));
+}
+
+/// Formats a Unix timestamp as a `YYYY-MM-DD` date.
+///
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn date(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_portion = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_portion + 2) / 5 + 1;
+    let month = if month_portion < 10 {
+        month_portion + 3
+    } else {
+        month_portion - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
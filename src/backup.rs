@@ -0,0 +1,363 @@
+//! Backup and restore of an installation.
+
+use crate::{
+    config::Config,
+    env::create_export_file,
+    error::Error,
+    host_triple::get_host_triple,
+    toolchain::{get_config_exports, rust::get_rustup_home},
+};
+use flate2::{bufread::GzDecoder, write::GzEncoder, Compression};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::os::unix::fs::symlink;
+use std::{
+    fs::{copy, create_dir_all, read_link, rename, File},
+    io::{BufReader, Read, Write},
+    path::{Component, Path, PathBuf},
+};
+use tar::{Archive, Builder, Header};
+use tempfile::Builder as TempBuilder;
+use xz2::{read::XzDecoder, write::XzEncoder};
+
+/// Name of the archive entry with the backup metadata.
+const METADATA_ENTRY: &str = "espup-backup.json";
+/// Name of the archive entry with the config of the installation.
+const CONFIG_ENTRY: &str = "config.toml";
+/// Name of the archive entry with the export file.
+const EXPORT_FILE_ENTRY: &str = "export";
+/// Prefix of the archive entries of the toolchain directory.
+const TOOLCHAIN_ENTRY: &str = "toolchain";
+
+/// Description of the backed up installation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupMetadata {
+    /// Host triple the installation was made for.
+    pub host_triple: String,
+    /// Toolchain name, the name of the toolchain directory.
+    pub name: String,
+    /// Target of the LLVM symlink relative to the toolchain directory, if it existed.
+    pub llvm_link: Option<PathBuf>,
+}
+
+/// Archives the toolchain directory, the export file and the config into a `.tar.gz`, `.tar.xz`
+/// or `.tar.zst` file, recording the LLVM symlink.
+pub fn backup(
+    file: &Path,
+    toolchain_path: &Path,
+    export_file: &Path,
+    llvm_link: &Path,
+    config_file: Option<&Path>,
+) -> Result<(), Error> {
+    info!(
+        "Backing up '{}' into '{}'",
+        toolchain_path.display(),
+        file.display()
+    );
+    let metadata = BackupMetadata {
+        host_triple: get_host_triple(None)?.to_string(),
+        name: toolchain_path
+            .file_name()
+            .ok_or_else(|| Error::MissingToolchain(toolchain_path.display().to_string()))?
+            .to_string_lossy()
+            .into_owned(),
+        llvm_link: read_link(llvm_link)
+            .ok()
+            .and_then(|target| Some(target.strip_prefix(toolchain_path).ok()?.to_path_buf())),
+    };
+
+    let writer: Box<dyn Write> = match get_extension(file)? {
+        "gz" => Box::new(GzEncoder::new(File::create(file)?, Compression::default())),
+        "zst" => Box::new(zstd::Encoder::new(File::create(file)?, 0)?.auto_finish()),
+        _ => Box::new(XzEncoder::new(File::create(file)?, 6)),
+    };
+    let mut builder = Builder::new(writer);
+    builder.follow_symlinks(false);
+
+    let json = serde_json::to_vec_pretty(&metadata).unwrap();
+    let mut header = Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, METADATA_ENTRY, json.as_slice())?;
+
+    builder.append_dir_all(TOOLCHAIN_ENTRY, toolchain_path)?;
+    if export_file.exists() {
+        builder.append_path_with_name(export_file, EXPORT_FILE_ENTRY)?;
+    }
+    if let Some(config_file) = config_file.filter(|config_file| config_file.exists()) {
+        builder.append_path_with_name(config_file, CONFIG_ENTRY)?;
+    }
+    builder.into_inner()?.flush()?;
+
+    info!("Backup successfully created!");
+    Ok(())
+}
+
+/// Restores a backup created with [`backup`] into `install_path`, or the rustup home, returning
+/// the restored toolchain directory.
+///
+/// Only the toolchain directory comes from the archive: the export file, the LLVM symlink and
+/// the config are written to the given paths. The export file is regenerated from the config,
+/// if archived, to point at the restored toolchain.
+pub fn restore(
+    file: &Path,
+    install_path: Option<&Path>,
+    export_file: &Path,
+    llvm_link: &Path,
+    config_path: Option<&Path>,
+) -> Result<PathBuf, Error> {
+    info!("Restoring backup '{}'", file.display());
+    let invalid = || Error::InvalidBackup(file.display().to_string());
+    let reader = BufReader::new(File::open(file)?);
+    let reader: Box<dyn Read> = match get_extension(file)? {
+        "gz" => Box::new(GzDecoder::new(reader)),
+        "zst" => Box::new(zstd::Decoder::new(reader)?),
+        _ => Box::new(XzDecoder::new(reader)),
+    };
+    let mut archive = Archive::new(reader);
+    let mut entries = archive.entries()?;
+
+    let mut metadata_entry = entries.next().ok_or_else(invalid)??;
+    if metadata_entry.path()?.as_ref() != Path::new(METADATA_ENTRY) {
+        return Err(invalid());
+    }
+    let mut json = String::new();
+    metadata_entry.read_to_string(&mut json)?;
+    let metadata: BackupMetadata = serde_json::from_str(&json).map_err(|_| invalid())?;
+    let mut name_components = Path::new(&metadata.name).components();
+    if !matches!(
+        (name_components.next(), name_components.next()),
+        (Some(Component::Normal(_)), None)
+    ) || !metadata.llvm_link.as_deref().map_or(true, is_relative_path)
+    {
+        return Err(invalid());
+    }
+
+    let host_triple = get_host_triple(None)?.to_string();
+    if metadata.host_triple != host_triple {
+        return Err(Error::IncompatibleBackup(metadata.host_triple, host_triple));
+    }
+    let toolchains_dir = match install_path {
+        Some(install_path) => install_path.to_path_buf(),
+        None => get_rustup_home()?.join("toolchains"),
+    };
+    let toolchain_dir = toolchains_dir.join(&metadata.name);
+    if toolchain_dir.exists() {
+        return Err(Error::RestoreDestinationExists(
+            toolchain_dir.display().to_string(),
+        ));
+    }
+
+    // Unpack next to the destination, so nothing is written outside of it and the toolchain
+    // directory only appears once it's complete.
+    create_dir_all(&toolchains_dir)
+        .map_err(|_| Error::CreateDirectory(toolchains_dir.display().to_string()))?;
+    let scratch_dir = TempBuilder::new()
+        .prefix(".espup-restore-")
+        .tempdir_in(&toolchains_dir)?;
+    let mut config = None;
+    for entry in entries {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        if !is_relative_path(&path) {
+            return Err(invalid());
+        }
+        if path == Path::new(CONFIG_ENTRY) {
+            let mut config_str = String::new();
+            entry.read_to_string(&mut config_str)?;
+            config = Some(toml::from_str::<Config>(&config_str).map_err(|_| invalid())?);
+        } else if path.starts_with(TOOLCHAIN_ENTRY) || path == Path::new(EXPORT_FILE_ENTRY) {
+            debug!("Restoring '{}'", path.display());
+            if !entry.unpack_in(scratch_dir.path())? {
+                return Err(invalid());
+            }
+        }
+    }
+    rename(scratch_dir.path().join(TOOLCHAIN_ENTRY), &toolchain_dir).map_err(|_| invalid())?;
+
+    let restored_export_file = scratch_dir.path().join(EXPORT_FILE_ENTRY);
+    if restored_export_file.exists() {
+        if let Some(parent) = export_file.parent() {
+            create_dir_all(parent)
+                .map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        }
+        copy(&restored_export_file, export_file)?;
+    }
+    #[cfg(unix)]
+    if let (Some(target), false) = (&metadata.llvm_link, llvm_link.is_symlink()) {
+        if let Some(parent) = llvm_link.parent() {
+            create_dir_all(parent)
+                .map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        }
+        symlink(toolchain_dir.join(target), llvm_link)?;
+    }
+    #[cfg(windows)]
+    let _ = llvm_link;
+    if let Some(mut config) = config {
+        config.name = metadata.name;
+        config.install_path = install_path.map(Path::to_path_buf);
+        config.export_file = export_file.to_path_buf();
+        if install_path.is_none() && config.rustup_home.is_some() {
+            config.rustup_home = Some(get_rustup_home()?);
+        }
+        config.save(config_path)?;
+        create_export_file(&config.export_file, &get_config_exports(&config)?)?;
+    }
+
+    info!("Backup successfully restored!");
+    Ok(toolchain_dir)
+}
+
+/// Checks that a path of the archive stays inside the directory it's unpacked to.
+fn is_relative_path(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Gets the compression extension of a backup file.
+fn get_extension(file: &Path) -> Result<&str, Error> {
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some(extension @ ("gz" | "xz" | "zst")) => Ok(extension),
+        extension => Err(Error::UnsuportedFileExtension(
+            extension.unwrap_or_default().to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        backup::{backup, is_relative_path, restore},
+        config::Config,
+    };
+    use std::{
+        fs::{create_dir_all, read_to_string, remove_dir_all, write},
+        path::Path,
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let install_path = temp_dir.path().join("toolchains");
+        let toolchain_path = install_path.join("esp");
+        create_dir_all(toolchain_path.join("bin")).unwrap();
+        write(toolchain_path.join("bin/rustc"), "rustc").unwrap();
+        let export_file = temp_dir.path().join("export-esp.sh");
+        write(&export_file, "export PATH=\"/tmp:$PATH\"").unwrap();
+        let llvm_link = temp_dir.path().join("esp-clang");
+        let config_path = temp_dir.path().join("esp.toml");
+
+        for extension in ["tar.gz", "tar.xz", "tar.zst"] {
+            let file = temp_dir.path().join(format!("backup.{extension}"));
+            backup(&file, &toolchain_path, &export_file, &llvm_link, None).unwrap();
+            assert!(restore(
+                &file,
+                Some(&install_path),
+                &export_file,
+                &llvm_link,
+                Some(&config_path)
+            )
+            .is_err());
+
+            remove_dir_all(&toolchain_path).unwrap();
+            write(&export_file, "").unwrap();
+            let toolchain_dir = restore(
+                &file,
+                Some(&install_path),
+                &export_file,
+                &llvm_link,
+                Some(&config_path),
+            )
+            .unwrap();
+            assert_eq!(toolchain_dir, toolchain_path);
+            assert!(!llvm_link.exists());
+            assert!(!config_path.exists());
+            assert_eq!(
+                read_to_string(toolchain_path.join("bin/rustc")).unwrap(),
+                "rustc"
+            );
+            assert_eq!(
+                read_to_string(&export_file).unwrap(),
+                "export PATH=\"/tmp:$PATH\""
+            );
+        }
+
+        assert!(backup(
+            &temp_dir.path().join("backup.zip"),
+            &toolchain_path,
+            &export_file,
+            &llvm_link,
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_restore_into_another_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let toolchain_path = temp_dir.path().join("toolchains/esp");
+        create_dir_all(toolchain_path.join("bin")).unwrap();
+        write(toolchain_path.join("bin/rustc"), "rustc").unwrap();
+        let export_file = temp_dir.path().join("export-esp.sh");
+        write(&export_file, "export PATH=\"/old:$PATH\"").unwrap();
+        let config_file = temp_dir.path().join("esp.toml");
+        write(
+            &config_file,
+            format!(
+                r#"name = "esp"
+xtensa_rust_version = "1.82.0.3"
+nightly_version = "nightly"
+targets = ["esp32c3"]
+esp_riscv_gcc = true
+extended_llvm = false
+install_path = "{}"
+linker = "gcc"
+std = false
+export_file = "{}"
+"#,
+                toolchain_path.parent().unwrap().display(),
+                export_file.display()
+            ),
+        )
+        .unwrap();
+        let file = temp_dir.path().join("backup.tar.gz");
+        backup(
+            &file,
+            &toolchain_path,
+            &export_file,
+            &temp_dir.path().join("esp-clang"),
+            Some(&config_file),
+        )
+        .unwrap();
+
+        let install_path = temp_dir.path().join("restored");
+        let restored_export_file = temp_dir.path().join("restored/export-esp.sh");
+        let config_path = temp_dir.path().join("restored/esp.toml");
+        let toolchain_dir = restore(
+            &file,
+            Some(&install_path),
+            &restored_export_file,
+            &temp_dir.path().join("restored/esp-clang"),
+            Some(&config_path),
+        )
+        .unwrap();
+        assert_eq!(toolchain_dir, install_path.join("esp"));
+        let config = Config::load("esp", Some(&config_path)).unwrap().unwrap();
+        assert_eq!(config.install_path, Some(install_path));
+        assert_eq!(config.export_file, restored_export_file);
+        let exports = read_to_string(&restored_export_file).unwrap();
+        assert!(exports.contains(&toolchain_dir.display().to_string()));
+        assert!(!exports.contains("/old"));
+    }
+
+    #[test]
+    fn test_is_relative_path() {
+        assert!(is_relative_path(Path::new("toolchain/bin/rustc")));
+        assert!(is_relative_path(Path::new("./export")));
+        assert!(!is_relative_path(Path::new("toolchain/../../.bashrc")));
+        assert!(!is_relative_path(Path::new("/home/user/.profile")));
+    }
+}
@@ -1,30 +1,362 @@
 //! Command line interface.
 
-use crate::targets::{parse_targets, Target};
-use clap::Parser;
+use crate::{
+    targets::{parse_targets, Target},
+    toolchain::llvm::LLVM_VERSIONS,
+};
+use clap::{ArgAction, Parser, ValueEnum};
 use clap_complete::Shell;
+use serde::{Deserialize, Serialize};
 use std::{collections::HashSet, path::PathBuf};
 
+#[derive(Debug, Parser)]
+pub struct BackupOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// Relative or full path for the export file to back up, instead of the one of the config.
+    #[arg(short = 'f', long, env = "ESPUP_EXPORT_FILE")]
+    pub export_file: Option<PathBuf>,
+    /// Backup file to create, ending in `.tar.gz`, `.tar.xz` or `.tar.zst`.
+    pub file: PathBuf,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
 #[derive(Debug, Parser)]
 pub struct CompletionsOpts {
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
     /// Verbosity level of the logs.
     #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
     pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
     /// Shell to generate completions for.
     pub shell: Shell,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct DoctorOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text.
+    #[default]
+    Human,
+    /// JSON document.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum ExportShell {
+    /// `export-esp.fish`, for fish.
+    Fish,
+    /// `export-esp.nu`, for nushell.
+    Nu,
+}
+
+impl ExportShell {
+    /// Extension of the export file for the shell.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportShell::Fish => "fish",
+            ExportShell::Nu => "nu",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Linker {
+    /// Espressif GCC linker (requires `--esp-riscv-gcc` for RISC-V targets).
+    #[default]
+    Gcc,
+    /// `rust-lld`, shipped with the nightly toolchain.
+    Lld,
+}
+
+#[derive(Debug, Parser)]
+pub struct DownloadOpts {
+    /// Target triple of the host.
+    #[arg(short = 'd', long, value_parser = ["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-pc-windows-gnu" , "x86_64-apple-darwin" , "aarch64-apple-darwin"])]
+    pub default_host: Option<String>,
+    /// Number of attempts for each download, retried with exponential backoff on network errors.
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+    pub download_attempts: u64,
+    /// Prints what would be downloaded, without downloading anything.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Downloads the Espressif RISC-V toolchain built with crosstool-ng.
+    #[arg(short = 'r', long)]
+    pub esp_riscv_gcc: bool,
+    /// Downloads the whole LLVM instead of only the libs.
+    #[arg(short = 'e', long)]
+    pub extended_llvm: bool,
+    /// Exits with an error if any warning was emitted during the run.
+    #[arg(long)]
+    pub fail_on_warning: bool,
+    /// GCC toolchain version to download (e.g. `14.2.0_20240906`), instead of the default one.
+    #[arg(long)]
+    pub gcc_version: Option<String>,
+    /// Maximum number of components to download at the same time [default: number of CPUs].
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub jobs: Option<u64>,
+    /// Linker used for RISC-V targets.
+    #[arg(long, value_enum, default_value_t = Linker::Gcc)]
+    pub linker: Linker,
+    /// GitHub release (page or API URL) to get the LLVM artifacts from, instead of the default one.
+    #[arg(long)]
+    pub llvm_release_url: Option<String>,
+    /// Major LLVM version to download, instead of the one matching the Xtensa Rust version.
+    #[arg(short = 'x', long, value_parser = LLVM_VERSIONS.map(|(major, _)| major))]
+    pub llvm_version: Option<String>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Base URL of a mirror of the GitHub releases to download the Xtensa Rust, LLVM and GCC toolchains from.
+    #[arg(long, env = "ESPUP_MIRROR_URL")]
+    pub mirror_url: Option<String>,
+    /// Skips the SHA-256 verification of the Xtensa Rust and LLVM artifacts, for mirrors that don't publish checksums.
+    #[arg(long)]
+    pub no_verify: bool,
+    /// Directory to download the artifacts into, to be used as `--bundle-dir` of an `--offline` installation.
+    #[arg(long)]
+    pub output_dir: PathBuf,
+    /// Proxy to use for all the downloads, instead of the ones of `HTTPS_PROXY`/`HTTP_PROXY`. Hosts in `NO_PROXY` are still reached directly.
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Looks up the latest Xtensa Rust version again, instead of using the cached one.
+    #[arg(long)]
+    pub refresh: bool,
+    /// Skips parsing Xtensa Rust version.
+    #[arg(short = 'k', long, requires = "toolchain_version")]
+    pub skip_version_parse: bool,
+    /// Only downloads the toolchains required for STD applications.
+    #[arg(short = 's', long)]
+    pub std: bool,
+    /// Comma or space separated list of targets [esp32,esp32c2,esp32c3,esp32c6,esp32h2,esp32s2,esp32s3,esp32p4,all].
+    #[arg(short = 't', long, default_value = "all", value_parser = parse_targets)]
+    pub targets: HashSet<Target>,
+    /// Downloads for the chips of the `build.target` of the `.cargo/config.toml` in the current directory, instead of `--targets`.
+    #[arg(long, conflicts_with = "targets")]
+    pub targets_from_cargo: bool,
+    /// Xtensa Rust toolchain version.
+    #[arg(short = 'v', long)]
+    pub toolchain_version: Option<String>,
+    /// Logs more details, `--verbose` for the debug logs and `--verbose --verbose` for the trace ones
+    /// (`-v` is `--toolchain-version`).
+    #[arg(long, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// Checks that every artifact needed for the host is available before downloading anything.
+    #[arg(long)]
+    pub verify_host_artifacts: bool,
+    /// Hours to reuse the cached latest Xtensa Rust version for.
+    #[arg(
+        long,
+        value_name = "HOURS",
+        default_value_t = 24,
+        env = "ESPUP_VERSION_CACHE_TTL"
+    )]
+    pub version_cache_ttl: u64,
+    /// GitHub release (page or API URL) to get the Xtensa Rust artifacts from, instead of the default one.
+    #[arg(long)]
+    pub xtensa_release_url: Option<String>,
+}
+
+impl From<DownloadOpts> for InstallOpts {
+    /// Gets the options of a `--download-only` installation into the output directory.
+    fn from(args: DownloadOpts) -> Self {
+        Self {
+            bundle_dir: Some(args.output_dir),
+            default_host: args.default_host,
+            download_attempts: args.download_attempts,
+            download_only: true,
+            dry_run: args.dry_run,
+            esp_riscv_gcc: args.esp_riscv_gcc,
+            extended_llvm: args.extended_llvm,
+            fail_on_warning: args.fail_on_warning,
+            gcc_version: args.gcc_version,
+            jobs: args.jobs,
+            linker: args.linker,
+            llvm_release_url: args.llvm_release_url,
+            llvm_version: args.llvm_version,
+            log_file: args.log_file,
+            log_level: args.log_level,
+            log_module: args.log_module,
+            mirror_url: args.mirror_url,
+            no_verify: args.no_verify,
+            proxy: args.proxy,
+            quiet: args.quiet,
+            refresh: args.refresh,
+            skip_version_parse: args.skip_version_parse,
+            std: args.std,
+            targets: args.targets,
+            targets_from_cargo: args.targets_from_cargo,
+            toolchain_version: args.toolchain_version,
+            verbose: args.verbose,
+            verify_host_artifacts: args.verify_host_artifacts,
+            version_cache_ttl: args.version_cache_ttl,
+            xtensa_release_url: args.xtensa_release_url,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportOnlyOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct InfoOpts {
+    /// Prints the fields interpolated into a template, e.g. `'{xtensa_rust_version} {llvm_path}'`.
+    #[arg(long, conflicts_with = "output")]
+    pub format: Option<String>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Output format.
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Looks up the latest Xtensa Rust version again, instead of using the cached one.
+    #[arg(long)]
+    pub refresh: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// Hours to reuse the cached latest Xtensa Rust version for.
+    #[arg(
+        long,
+        value_name = "HOURS",
+        default_value_t = 24,
+        env = "ESPUP_VERSION_CACHE_TTL"
+    )]
+    pub version_cache_ttl: u64,
 }
 
 #[derive(Debug, Parser)]
 pub struct InstallOpts {
+    /// Accepts the licenses of the installed components without prompting.
+    #[arg(long)]
+    pub accept_license: bool,
+    /// Directory of the bundle of artifacts: read by `--offline` installations, written by `--download-only` and `espup download`.
+    #[arg(long)]
+    pub bundle_dir: Option<PathBuf>,
+    /// Cargo home directory to use for the installation, instead of `CARGO_HOME` or `~/.cargo`.
+    #[arg(long)]
+    pub cargo_home: Option<PathBuf>,
+    /// Path of a JSON manifest listing the files installed by each component.
+    #[arg(long)]
+    pub components_manifest: Option<PathBuf>,
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
     /// Target triple of the host.
     #[arg(short = 'd', long, value_parser = ["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-pc-windows-gnu" , "x86_64-apple-darwin" , "aarch64-apple-darwin"])]
     pub default_host: Option<String>,
+    /// Number of attempts for each download, retried with exponential backoff on network errors.
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+    pub download_attempts: u64,
+    /// Prints what the installation would do, without downloading or installing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Only downloads the artifacts of the selected components into `~/.espup/dist`, without installing them.
+    #[arg(long)]
+    pub download_only: bool,
     /// Install Espressif RISC-V toolchain built with croostool-ng
     ///
     /// Only install this if you don't want to use the systems RISC-V toolchain
     #[arg(short = 'r', long)]
     pub esp_riscv_gcc: bool,
-    /// Relative or full path for the export file that will be generated. If no path is provided, the file will be generated under home directory (https://docs.rs/dirs/latest/dirs/fn.home_dir.html).
+    /// Relative or full path for the export file that will be generated. If no path is provided, the file will be generated under home directory (https://docs.rs/dirs/latest/dirs/fn.home_dir.html). Use `-` to write it to stdout instead.
     #[arg(short = 'f', long, env = "ESPUP_EXPORT_FILE")]
     pub export_file: Option<PathBuf>,
     /// Extends the LLVM installation.
@@ -32,17 +364,114 @@ pub struct InstallOpts {
     /// This will install the whole LLVM instead of only installing the libs.
     #[arg(short = 'e', long)]
     pub extended_llvm: bool,
+    /// Exits with an error if any warning was emitted during the run.
+    #[arg(long)]
+    pub fail_on_warning: bool,
+    /// Overwrites the export file even if it was not generated by espup, reinstalls the components that are already installed, and installs even if the disk space seems insufficient.
+    #[arg(long)]
+    pub force: bool,
+    /// TOML install profile to read the options from, the ones given on the command line take precedence.
+    ///
+    /// Supported keys: `default-host`, `esp-riscv-gcc`, `export-file`, `extended-llvm`, `gcc-version`, `linker`, `llvm-version`, `name`, `nightly-version`, `no-modify-path`, `std`, `targets` and `toolchain-version`.
+    #[arg(long, value_name = "FILE")]
+    pub from_file: Option<PathBuf>,
+    /// GCC toolchain version to install (e.g. `14.2.0_20240906`), instead of the default one.
+    ///
+    /// It must be a release of https://github.com/espressif/crosstool-NG.
+    #[arg(long)]
+    pub gcc_version: Option<String>,
+    /// Directory to install the toolchain into, as `<DIR>/<name>`, instead of the rustup home. Xtensa Rust is linked to rustup.
+    #[arg(long, env = "ESPUP_HOME")]
+    pub install_path: Option<PathBuf>,
+    /// Maximum number of components to download and install at the same time [default: number of CPUs].
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub jobs: Option<u64>,
+    /// Keeps the components installed before a failure, to resume the installation, instead of removing the new toolchain.
+    #[arg(long)]
+    pub keep_partial: bool,
+    /// Linker used for RISC-V targets.
+    ///
+    /// `lld` exports the Cargo linker configuration for the RISC-V targets and skips the Espressif RISC-V GCC installation. It's faster and doesn't require any extra download, but it can't be used by projects that link C code built with GCC.
+    #[arg(long, value_enum, default_value_t = Linker::Gcc)]
+    pub linker: Linker,
+    /// GitHub release (page or API URL) to get the LLVM artifacts from, instead of the default one.
+    #[arg(long)]
+    pub llvm_release_url: Option<String>,
+    /// Major LLVM version to install, instead of the one matching the Xtensa Rust version.
+    #[arg(short = 'x', long, value_parser = LLVM_VERSIONS.map(|(major, _)| major))]
+    pub llvm_version: Option<String>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
     /// Verbosity level of the logs.
     #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
     pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Base URL of a mirror of the GitHub releases to download the Xtensa Rust, LLVM and GCC toolchains from.
+    ///
+    /// The mirror must keep the GitHub layout: `https://github.com/<owner>/<repo>/releases/download/<tag>/<file>` is downloaded from `<MIRROR_URL>/<owner>/<repo>/releases/download/<tag>/<file>`.
+    #[arg(long, env = "ESPUP_MIRROR_URL")]
+    pub mirror_url: Option<String>,
     /// Xtensa Rust toolchain name.
     #[arg(short = 'a', long, default_value = "esp")]
     pub name: String,
+    /// Doesn't modify the user environment variables on Windows, the export file has to be run manually instead.
+    ///
+    /// On other platforms espup never modifies the shell configuration.
+    #[arg(long)]
+    pub no_modify_path: bool,
+    /// Skips the SHA-256 verification of the Xtensa Rust and LLVM artifacts, for mirrors that don't publish checksums.
+    #[arg(long)]
+    pub no_verify: bool,
     /// Nightly Rust toolchain version.
     ///
     /// Note that only RISC-V targets use nightly Rust channel.
     #[arg(short = 'n', long, default_value = "nightly")]
     pub nightly_version: String,
+    /// Installs from the artifacts of `--bundle-dir` instead of downloading them, for machines without internet access.
+    ///
+    /// Create the bundle with `espup download` on a machine with internet access. `--toolchain-version` must be a full version, like `1.82.0.3`, as it can't be checked online. RISC-V targets are still installed with rustup.
+    #[arg(long, requires_all = ["bundle_dir", "toolchain_version"])]
+    pub offline: bool,
+    /// Format of the output: logs and instructions, or a JSON document describing the installation.
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+    /// Removes the artifacts of `~/.espup/dist` older than the given number of days, except the ones of the selected components.
+    #[arg(long, value_name = "DAYS")]
+    pub prune_dist_after: Option<u64>,
+    /// Proxy to use for all the downloads, instead of the ones of `HTTPS_PROXY`/`HTTP_PROXY`. Hosts in `NO_PROXY` are still reached directly.
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Looks up the latest Xtensa Rust version again, instead of using the cached one.
+    #[arg(long)]
+    pub refresh: bool,
+    /// Also adds the RISC-V targets to the stable toolchain.
+    #[arg(long)]
+    pub riscv_on_stable: bool,
+    /// Rustup home directory to use for the installation, instead of `RUSTUP_HOME` or `~/.rustup`.
+    #[arg(long)]
+    pub rustup_home: Option<PathBuf>,
+    /// Updates the nightly toolchain with `rustup update` before installing the RISC-V targets.
+    #[arg(long)]
+    pub rustup_update: bool,
+    /// Builds and links a `no_std` test binary for one of the targets after the installation, to check that the toolchains work.
+    #[arg(long)]
+    pub self_test: bool,
+    /// Sets the Xtensa Rust toolchain as the default rustup toolchain, the previous default is restored when uninstalling.
+    #[arg(long)]
+    pub set_default: bool,
+    /// Shells to also write an export file for, next to the export file. Installed shells are detected automatically.
+    #[cfg(unix)]
+    #[arg(long, value_enum)]
+    pub shell: Vec<ExportShell>,
+    /// Prints the ordered steps of the installation before running them.
+    #[arg(long)]
+    pub show_plan: bool,
     /// Skips parsing Xtensa Rust version.
     #[arg(short = 'k', long, requires = "toolchain_version")]
     pub skip_version_parse: bool,
@@ -51,20 +480,246 @@ pub struct InstallOpts {
     /// With this option, espup will skip GCC installation (it will be handled by esp-idf-sys), hence you won't be able to build no_std applications.
     #[arg(short = 's', long)]
     pub std: bool,
+    /// Makes the installed GCC toolchains match `--targets`, removing the ones that are no longer needed.
+    #[arg(long)]
+    pub sync_targets: bool,
     /// Comma or space separated list of targets [esp32,esp32c2,esp32c3,esp32c6,esp32h2,esp32s2,esp32s3,esp32p4,all].
+    ///
+    /// Use `xtensa` or `riscv` (or `all-xtensa` and `all-riscv`) to select all the chips of an architecture.
+    ///
+    /// Use `auto` to read the targets from the `package.metadata.espup.targets` table of the `Cargo.toml` in the current directory.
     #[arg(short = 't', long, default_value = "all", value_parser = parse_targets)]
     pub targets: HashSet<Target>,
+    /// Installs the chips of the `build.target` of the `.cargo/config.toml` in the current directory, instead of `--targets`.
+    #[arg(long, conflicts_with = "targets")]
+    pub targets_from_cargo: bool,
     /// Xtensa Rust toolchain version.
     #[arg(short = 'v', long)]
     pub toolchain_version: Option<String>,
+    /// Logs more details, `--verbose` for the debug logs and `--verbose --verbose` for the trace ones
+    /// (`-v` is `--toolchain-version`).
+    #[arg(long, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// Checks that every artifact needed for the host is available before downloading anything.
+    #[arg(long)]
+    pub verify_host_artifacts: bool,
+    /// Hours to reuse the cached latest Xtensa Rust version for.
+    #[arg(
+        long,
+        value_name = "HOURS",
+        default_value_t = 24,
+        env = "ESPUP_VERSION_CACHE_TTL"
+    )]
+    pub version_cache_ttl: u64,
+    /// Warns about toolchain binaries in PATH that may be used instead of the ones installed by espup.
+    ///
+    /// Combine with `--fail-on-warning` to fail on conflicts.
+    #[arg(long)]
+    pub warn_on_path_conflict: bool,
+    /// GitHub release (page or API URL) to get the Xtensa Rust artifacts from, instead of the default one.
+    #[arg(long)]
+    pub xtensa_release_url: Option<String>,
+    /// Assumes yes as the answer to confirmation prompts.
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+}
+
+impl Default for InstallOpts {
+    fn default() -> Self {
+        default_opts()
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct ListOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct ReinstallOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Xtensa Rust toolchain version to reinstall instead of the installed one.
+    #[arg(short = 'v', long)]
+    pub toolchain_version: Option<String>,
+    /// Logs more details, `--verbose` for the debug logs and `--verbose --verbose` for the trace ones
+    /// (`-v` is `--toolchain-version`).
+    #[arg(long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct RestoreOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// Relative or full path for the restored export file.
+    #[arg(short = 'f', long, env = "ESPUP_EXPORT_FILE")]
+    pub export_file: Option<PathBuf>,
+    /// Backup file to restore, created with `espup backup`.
+    pub file: PathBuf,
+    /// Directory to restore the toolchain into, as `<DIR>/<name>`, instead of the rustup home. Xtensa Rust is linked to rustup.
+    #[arg(long, env = "ESPUP_HOME")]
+    pub install_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct ShowConfigOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct TargetOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Comma or space separated list of targets [esp32,esp32c2,esp32c3,esp32c6,esp32h2,esp32s2,esp32s3,esp32p4,all].
+    ///
+    /// Use `xtensa` or `riscv` (or `all-xtensa` and `all-riscv`) to select all the chips of an architecture.
+    #[arg(value_parser = parse_targets)]
+    pub targets: HashSet<Target>,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// Assumes yes as the answer to confirmation prompts.
+    #[arg(short = 'y', long)]
+    pub yes: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct UninstallOpts {
+    /// Path of the config file recording the installation, instead of `<name>.toml` in `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`.
+    #[arg(long)]
+    pub config_path: Option<PathBuf>,
+    /// File to also write the logs to, including the debug ones.
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
     /// Verbosity level of the logs.
     #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
     pub log_level: String,
+    /// Per-module log filters (e.g. `espup::toolchain::llvm=debug,info`), overrides `--log-level`.
+    #[arg(long)]
+    pub log_module: Option<String>,
     /// Xtensa Rust toolchain name.
     #[arg(short = 'a', long, default_value = "esp")]
     pub name: String,
+    /// Format of the output: logs, or a JSON document describing the uninstallation.
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+    /// Only prints errors, for use in scripts.
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+    /// Logs more details, `-v` for the debug logs and `-vv` for the trace ones.
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+impl Default for UninstallOpts {
+    fn default() -> Self {
+        default_opts()
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct UpdateOpts {
+    #[command(flatten)]
+    pub install: InstallOpts,
+    /// Updates espup itself to the latest release instead of the Xtensa Rust toolchain.
+    #[arg(long = "self")]
+    pub self_update: bool,
+    /// espup version to update to when using `--self`.
+    #[arg(long, requires = "self_update")]
+    pub self_version: Option<String>,
+}
+
+/// Gets the default values of the command line options, ignoring the environment variables.
+fn default_opts<T: Parser>() -> T {
+    let command = T::command().mut_args(|arg| arg.env(None));
+    T::from_arg_matches(&command.get_matches_from(["espup"])).unwrap()
 }
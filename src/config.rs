@@ -0,0 +1,425 @@
+//! Record of the options used by the installations.
+
+use crate::{
+    cli::{InstallOpts, Linker},
+    error::Error,
+    targets::Target,
+    toolchain::rust::get_rustup_toolchain_dir,
+};
+use clap::Parser;
+use directories::BaseDirs;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    env,
+    ffi::OsString,
+    fs::{create_dir_all, read_to_string, remove_file},
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+
+/// Options of an installation, saved in `<name>.toml` of the config directory (or `--config-path`)
+/// to be able to repeat it. See [`get_config_dir`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Xtensa Rust toolchain name.
+    pub name: String,
+    /// Installed Xtensa Rust version.
+    pub xtensa_rust_version: String,
+    /// Nightly Rust toolchain version used by the RISC-V targets.
+    pub nightly_version: String,
+    /// Installed targets.
+    pub targets: BTreeSet<Target>,
+    /// Whether the Espressif RISC-V GCC was installed.
+    pub esp_riscv_gcc: bool,
+    /// Whether the whole LLVM was installed.
+    pub extended_llvm: bool,
+    /// GCC version selected with `--gcc-version`.
+    #[serde(default)]
+    pub gcc_version: Option<String>,
+    /// Directory the toolchain was installed into with `--install-path`.
+    #[serde(default)]
+    pub install_path: Option<PathBuf>,
+    /// Cargo home directory selected with `--cargo-home`, as an absolute path.
+    #[serde(default)]
+    pub cargo_home: Option<PathBuf>,
+    /// Rustup home directory selected with `--rustup-home`, as an absolute path.
+    #[serde(default)]
+    pub rustup_home: Option<PathBuf>,
+    /// LLVM major version selected with `--llvm-version`.
+    #[serde(default)]
+    pub llvm_version: Option<String>,
+    /// GitHub release the LLVM was installed from with `--llvm-release-url`.
+    #[serde(default)]
+    pub llvm_release_url: Option<String>,
+    /// GitHub release the Xtensa Rust toolchain was installed from with `--xtensa-release-url`.
+    #[serde(default)]
+    pub xtensa_release_url: Option<String>,
+    /// Linker used by the RISC-V targets.
+    pub linker: Linker,
+    /// Whether the RISC-V targets were also added to the stable toolchain.
+    #[serde(default)]
+    pub riscv_on_stable: bool,
+    /// Whether only the toolchains required for STD applications were installed.
+    pub std: bool,
+    /// Export file generated by the installation.
+    pub export_file: PathBuf,
+    /// Whether the licenses of the installed components were accepted.
+    #[serde(default)]
+    pub license_accepted: bool,
+    /// Whether the user environment variables were left untouched on Windows.
+    #[serde(default)]
+    pub no_modify_path: bool,
+    /// Whether the toolchain was made the default rustup toolchain with `--set-default`.
+    #[serde(default)]
+    pub set_default: bool,
+    /// Default rustup toolchain replaced with `--set-default`, restored when uninstalling.
+    #[serde(default)]
+    pub previous_default_toolchain: Option<String>,
+    /// Time of the first installation, in seconds since the UNIX epoch.
+    #[serde(default)]
+    pub installed_at: Option<u64>,
+    /// Version of espup that wrote the config.
+    #[serde(default)]
+    pub espup_version: Option<String>,
+}
+
+/// Gets the directory of the configs: `ESPUP_CONFIG_HOME`, `$XDG_CONFIG_HOME/espup` or `~/.espup`,
+/// the first one that is set.
+pub fn get_config_dir() -> Result<PathBuf, Error> {
+    resolve_config_dir(
+        env::var_os("ESPUP_CONFIG_HOME"),
+        env::var_os("XDG_CONFIG_HOME"),
+    )
+}
+
+/// Gets the directory of the configs from the values of `ESPUP_CONFIG_HOME` and
+/// `XDG_CONFIG_HOME`.
+fn resolve_config_dir(
+    espup_config_home: Option<OsString>,
+    xdg_config_home: Option<OsString>,
+) -> Result<PathBuf, Error> {
+    if let Some(config_home) = espup_config_home.filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(config_home));
+    }
+    if let Some(config_home) = xdg_config_home.filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(config_home).join("espup"));
+    }
+    get_legacy_config_dir().ok_or(Error::NoHomeDir)
+}
+
+/// Gets `~/.espup`, where the configs were kept before `ESPUP_CONFIG_HOME` and `XDG_CONFIG_HOME`
+/// were supported.
+fn get_legacy_config_dir() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.home_dir().join(".espup"))
+}
+
+impl Config {
+    /// Gets the path of the config of a toolchain, `config_path` if provided.
+    ///
+    /// Configs that only exist in `~/.espup` are still found there.
+    pub fn get_path(name: &str, config_path: Option<&Path>) -> Result<PathBuf, Error> {
+        if let Some(config_path) = config_path {
+            return Ok(config_path.to_path_buf());
+        }
+        let file_name = format!("{name}.toml");
+        let path = get_config_dir()?.join(&file_name);
+        if !path.exists() {
+            if let Some(legacy_path) = get_legacy_config_dir()
+                .map(|dir| dir.join(&file_name))
+                .filter(|legacy_path| legacy_path.exists())
+            {
+                return Ok(legacy_path);
+            }
+        }
+        Ok(path)
+    }
+
+    /// Loads the config of a toolchain, if it was installed by espup.
+    pub fn load(name: &str, config_path: Option<&Path>) -> Result<Option<Self>, Error> {
+        let path = Self::get_path(name, config_path)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        debug!("Loading config from '{}'", path.display());
+        toml::from_str(&read_to_string(&path)?)
+            .map(Some)
+            .map_err(|_| Error::InvalidConfig(path.display().to_string()))
+    }
+
+    /// Saves the config in the path of its toolchain.
+    pub fn save(&self, config_path: Option<&Path>) -> Result<(), Error> {
+        let path = Self::get_path(&self.name, config_path)?;
+        debug!("Saving config to '{}'", path.display());
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        create_dir_all(parent).map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        // Write a sibling file and rename it, so an interrupted save never truncates the config
+        let mut file = NamedTempFile::new_in(parent)?;
+        file.write_all(toml::to_string(self).unwrap().as_bytes())?;
+        // Flush the content before the rename, so a crash can't leave an empty config behind
+        file.as_file().sync_all()?;
+        file.persist(&path).map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    /// Removes the config of a toolchain, if any.
+    pub fn remove(name: &str, config_path: Option<&Path>) -> Result<(), Error> {
+        let path = Self::get_path(name, config_path)?;
+        if path.exists() {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the directory of the toolchain, in its `--install-path` or in the rustup home.
+    pub fn toolchain_dir(&self) -> Result<PathBuf, Error> {
+        match &self.install_path {
+            Some(install_path) => Ok(install_path.join(&self.name)),
+            None => match &self.rustup_home {
+                Some(rustup_home) => Ok(rustup_home.join("toolchains").join(&self.name)),
+                None => get_rustup_toolchain_dir(&self.name),
+            },
+        }
+    }
+
+    /// Sets `CARGO_HOME` and `RUSTUP_HOME` to the ones of the installation, if recorded, for this
+    /// run and the rustup commands it spawns.
+    pub fn set_home_vars(&self) {
+        for (var, path) in [
+            ("CARGO_HOME", &self.cargo_home),
+            ("RUSTUP_HOME", &self.rustup_home),
+        ] {
+            if let Some(path) = path {
+                debug!("Setting {} to '{}'", var, path.display());
+                env::set_var(var, path);
+            }
+        }
+    }
+
+    /// Warns if the config was written by a newer minor or major version of espup.
+    pub fn check_espup_version(&self) {
+        let Some(espup_version) = &self.espup_version else {
+            return;
+        };
+        if is_newer_minor_version(espup_version, env!("CARGO_PKG_VERSION")) {
+            warn!(
+                "Toolchain '{}' was installed with espup {}, newer than this one ({}), consider updating espup with `espup update --self`",
+                self.name,
+                espup_version,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+    }
+
+    /// Gets the install options that repeat the installation.
+    pub fn install_opts(&self) -> InstallOpts {
+        let mut args = InstallOpts::parse_from(["install"]);
+        args.name = self.name.clone();
+        args.toolchain_version = Some(self.xtensa_rust_version.clone());
+        self.apply(&mut args, |_| false);
+        args
+    }
+
+    /// Sets the options of the installation in `args`, except the ones that `is_set` reports as
+    /// set (e.g. from the command line), keeping the name and the Xtensa Rust version of `args`.
+    pub fn apply(&self, args: &mut InstallOpts, is_set: impl Fn(&str) -> bool) {
+        if !is_set("nightly_version") {
+            args.nightly_version = self.nightly_version.clone();
+        }
+        if !is_set("targets") && !is_set("targets_from_cargo") {
+            args.targets = self.targets.iter().copied().collect();
+        }
+        if !is_set("esp_riscv_gcc") {
+            args.esp_riscv_gcc = self.esp_riscv_gcc;
+        }
+        if !is_set("extended_llvm") {
+            args.extended_llvm = self.extended_llvm;
+        }
+        if !is_set("gcc_version") {
+            args.gcc_version = self.gcc_version.clone();
+        }
+        if !is_set("install_path") {
+            args.install_path = self.install_path.clone();
+        }
+        if !is_set("cargo_home") {
+            args.cargo_home = self.cargo_home.clone();
+        }
+        if !is_set("rustup_home") {
+            args.rustup_home = self.rustup_home.clone();
+        }
+        if !is_set("accept_license") {
+            args.accept_license = self.license_accepted;
+        }
+        if !is_set("llvm_version") {
+            args.llvm_version = self.llvm_version.clone();
+        }
+        if !is_set("llvm_release_url") {
+            args.llvm_release_url = self.llvm_release_url.clone();
+        }
+        // The release only has the assets of the recorded version
+        if !is_set("xtensa_release_url")
+            && args.toolchain_version.as_deref() == Some(self.xtensa_rust_version.as_str())
+        {
+            args.xtensa_release_url = self.xtensa_release_url.clone();
+        }
+        if !is_set("linker") {
+            args.linker = self.linker;
+        }
+        if !is_set("riscv_on_stable") {
+            args.riscv_on_stable = self.riscv_on_stable;
+        }
+        if !is_set("std") {
+            args.std = self.std;
+        }
+        if !is_set("export_file") {
+            args.export_file = Some(self.export_file.clone());
+        }
+        if !is_set("no_modify_path") {
+            args.no_modify_path = self.no_modify_path;
+        }
+        if !is_set("set_default") {
+            // Configs written before `set_default` was recorded only have the replaced toolchain
+            args.set_default = self.set_default || self.previous_default_toolchain.is_some();
+        }
+    }
+}
+
+/// Checks if `version` has a newer major or minor number than `current`, ignoring the patch.
+fn is_newer_minor_version(version: &str, current: &str) -> bool {
+    let minor_version = |version: &str| -> Option<(u64, u64)> {
+        let mut numbers = version.trim_start_matches('v').split('.');
+        Some((numbers.next()?.parse().ok()?, numbers.next()?.parse().ok()?))
+    };
+    match (minor_version(version), minor_version(current)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli::{InstallOpts, Linker},
+        config::{is_newer_minor_version, resolve_config_dir, Config},
+        targets::Target,
+    };
+    use clap::Parser;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_config_install_opts() {
+        let config = Config {
+            name: "esp-1.82".to_string(),
+            xtensa_rust_version: "1.82.0.3".to_string(),
+            nightly_version: "nightly-2024-11-01".to_string(),
+            targets: [Target::ESP32, Target::ESP32C3].into_iter().collect(),
+            esp_riscv_gcc: true,
+            extended_llvm: false,
+            gcc_version: None,
+            install_path: None,
+            cargo_home: None,
+            rustup_home: Some(PathBuf::from("/opt/rustup")),
+            llvm_version: Some("18".to_string()),
+            llvm_release_url: None,
+            xtensa_release_url: Some(
+                "https://github.com/esp-rs/rust-build/releases/tag/v1.82.0.3".to_string(),
+            ),
+            linker: Linker::Lld,
+            riscv_on_stable: true,
+            std: false,
+            export_file: PathBuf::from("/tmp/export-esp.sh"),
+            license_accepted: false,
+            no_modify_path: false,
+            set_default: false,
+            previous_default_toolchain: Some("stable".to_string()),
+            installed_at: Some(1_730_000_000),
+            espup_version: Some("0.14.0".to_string()),
+        };
+        let toml = toml::to_string(&config).unwrap();
+        assert!(toml.contains("targets = [\"esp32\", \"esp32c3\"]"));
+        assert_eq!(toml::from_str::<Config>(&toml).unwrap(), config);
+
+        let args = config.install_opts();
+        assert_eq!(args.name, "esp-1.82");
+        assert_eq!(args.toolchain_version.as_deref(), Some("1.82.0.3"));
+        assert_eq!(args.targets.len(), 2);
+        assert!(args.esp_riscv_gcc);
+        assert!(args.set_default);
+        assert_eq!(args.llvm_version.as_deref(), Some("18"));
+        assert_eq!(args.linker, Linker::Lld);
+        assert!(args.riscv_on_stable);
+        assert_eq!(
+            args.xtensa_release_url.as_deref(),
+            Some("https://github.com/esp-rs/rust-build/releases/tag/v1.82.0.3")
+        );
+        assert_eq!(args.export_file, Some(PathBuf::from("/tmp/export-esp.sh")));
+        assert_eq!(args.rustup_home, Some(PathBuf::from("/opt/rustup")));
+        assert_eq!(
+            config.toolchain_dir().unwrap(),
+            PathBuf::from("/opt/rustup/toolchains/esp-1.82")
+        );
+
+        // Options given on the command line are kept
+        let mut args = InstallOpts::parse_from(["update", "--std", "--targets", "esp32"]);
+        config.apply(&mut args, |id| id == "std" || id == "targets");
+        assert!(args.std);
+        assert_eq!(args.targets.len(), 1);
+        assert_eq!(args.linker, Linker::Lld);
+        assert_eq!(args.nightly_version, "nightly-2024-11-01");
+        assert!(args.toolchain_version.is_none());
+        assert!(args.xtensa_release_url.is_none());
+
+        // Made the default without replacing another toolchain
+        let first_default = Config {
+            set_default: true,
+            previous_default_toolchain: None,
+            ..config.clone()
+        };
+        assert!(first_default.install_opts().set_default);
+
+        // Custom config path
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("ci/espup.toml");
+        config.save(Some(&config_path)).unwrap();
+        assert_eq!(
+            Config::load("esp-1.82", Some(&config_path)).unwrap(),
+            Some(config)
+        );
+        Config::remove("esp-1.82", Some(&config_path)).unwrap();
+        assert!(Config::load("esp-1.82", Some(&config_path))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_is_newer_minor_version() {
+        assert!(is_newer_minor_version("0.15.0", "0.14.1"));
+        assert!(is_newer_minor_version("1.0.0", "0.14.1"));
+        assert!(!is_newer_minor_version("0.14.2", "0.14.1"));
+        assert!(!is_newer_minor_version("0.13.0", "0.14.1"));
+        assert!(!is_newer_minor_version("unknown", "0.14.1"));
+    }
+
+    #[test]
+    fn test_resolve_config_dir() {
+        assert_eq!(
+            resolve_config_dir(
+                Some("/tmp/espup-config".into()),
+                Some("/tmp/xdg-config".into())
+            )
+            .unwrap(),
+            PathBuf::from("/tmp/espup-config")
+        );
+        assert_eq!(
+            resolve_config_dir(Some("".into()), Some("/tmp/xdg-config".into())).unwrap(),
+            PathBuf::from("/tmp/xdg-config").join("espup")
+        );
+        assert!(resolve_config_dir(None, None).unwrap().ends_with(".espup"));
+    }
+}
@@ -0,0 +1,237 @@
+//! Diagnosis of an installation.
+
+use crate::{
+    cli::Linker,
+    config::Config,
+    env::is_stdout_export_file,
+    error::Error,
+    host_triple::get_host_triple,
+    targets::Target,
+    toolchain::{
+        gcc::{Gcc, RISCV_GCC, XTENSA_GCC},
+        llvm::Llvm,
+        rust::RISCV_TARGETS,
+    },
+};
+use log::debug;
+use std::{
+    env::consts::EXE_SUFFIX,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Result of a check of the installation.
+#[derive(Debug)]
+pub struct Check {
+    /// Description of what was checked.
+    pub name: String,
+    /// Whether the check passed.
+    pub passed: bool,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, passed: bool) -> Self {
+        Self {
+            name: name.into(),
+            passed,
+        }
+    }
+}
+
+/// Returns the output of a rustup command, empty if it fails.
+fn rustup_output(args: &[&str]) -> String {
+    debug!("Running 'rustup {}'", args.join(" "));
+    Command::new("rustup")
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .unwrap_or_default()
+}
+
+/// Returns whether a directory exists and isn't empty.
+fn is_populated_dir(path: &Path) -> bool {
+    path.read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Checks that the components of an installation are in place.
+pub fn diagnose(config: &Config) -> Result<Vec<Check>, Error> {
+    let host_triple = get_host_triple(None)?;
+    let toolchain_dir = config.toolchain_dir()?;
+    let has_xtensa = config.targets.iter().any(|t| t.is_xtensa());
+    let has_riscv = config.targets.iter().any(|t| t.is_riscv());
+    let mut checks = Vec::new();
+
+    if has_xtensa {
+        checks.push(Check::new(
+            format!("Xtensa Rust {} is installed", config.xtensa_rust_version),
+            toolchain_dir
+                .join("bin")
+                .join(format!("rustc{EXE_SUFFIX}"))
+                .is_file(),
+        ));
+        checks.push(Check::new(
+            format!("rustup lists the '{}' toolchain", config.name),
+            rustup_output(&["toolchain", "list"])
+                .lines()
+                .any(|line| line.split_whitespace().next() == Some(config.name.as_str())),
+        ));
+
+        let llvm = Llvm::new(
+            &toolchain_dir,
+            &host_triple,
+            config.extended_llvm,
+            &config.xtensa_rust_version,
+            config.llvm_version.as_deref(),
+        )?;
+        checks.push(Check::new(
+            format!("LLVM {} libraries are installed", llvm.version),
+            is_populated_dir(Path::new(&llvm.get_lib_path())),
+        ));
+        if config.extended_llvm {
+            checks.push(Check::new(
+                format!("LLVM {} binaries are installed", llvm.version),
+                Path::new(&llvm.get_bin_path()).is_file(),
+            ));
+        }
+    }
+
+    if has_riscv {
+        let installed_targets = rustup_output(&[
+            "target",
+            "list",
+            "--installed",
+            "--toolchain",
+            &config.nightly_version,
+        ]);
+        checks.push(Check::new(
+            format!(
+                "RISC-V targets are installed on '{}'",
+                config.nightly_version
+            ),
+            RISCV_TARGETS
+                .iter()
+                .all(|target| installed_targets.lines().any(|line| line.trim() == *target)),
+        ));
+        checks.push(Check::new(
+            format!("rust-src is installed on '{}'", config.nightly_version),
+            rustup_output(&[
+                "component",
+                "list",
+                "--installed",
+                "--toolchain",
+                &config.nightly_version,
+            ])
+            .lines()
+            .any(|line| line.trim() == "rust-src"),
+        ));
+    }
+
+    if !config.std {
+        let mut gcc_archs = Vec::new();
+        if has_xtensa {
+            gcc_archs.push(XTENSA_GCC);
+        }
+        if config.esp_riscv_gcc
+            && config.linker == Linker::Gcc
+            && config.targets.iter().any(|t| t != &Target::ESP32)
+        {
+            gcc_archs.push(RISCV_GCC);
+        }
+        for arch in gcc_archs {
+            let gcc = Gcc::new(
+                arch,
+                &host_triple,
+                &toolchain_dir,
+                config.gcc_version.as_deref(),
+            );
+            checks.push(Check::new(
+                format!("GCC ({arch}) is installed"),
+                is_populated_dir(Path::new(&gcc.get_bin_path())),
+            ));
+        }
+    }
+
+    if !is_stdout_export_file(&config.export_file) {
+        checks.push(Check::new(
+            format!("Export file '{}' exists", config.export_file.display()),
+            config.export_file.is_file(),
+        ));
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::Config,
+        doctor::diagnose,
+        host_triple::get_host_triple,
+        toolchain::gcc::{Gcc, XTENSA_GCC},
+    };
+    use std::fs::{create_dir_all, write};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_diagnose() {
+        let temp_dir = TempDir::new().unwrap();
+        let export_file = temp_dir.path().join("export-esp.sh");
+        let config: Config = toml::from_str(&format!(
+            r#"name = "esp-doctor-test"
+xtensa_rust_version = "1.82.0.3"
+nightly_version = "nightly"
+targets = ["esp32"]
+esp_riscv_gcc = false
+extended_llvm = false
+install_path = "{}"
+linker = "gcc"
+std = false
+export_file = "{}"
+"#,
+            temp_dir.path().display(),
+            export_file.display()
+        ))
+        .unwrap();
+        let toolchain_dir = config.toolchain_dir().unwrap();
+        create_dir_all(toolchain_dir.join("bin")).unwrap();
+        write(
+            toolchain_dir
+                .join("bin")
+                .join(format!("rustc{}", std::env::consts::EXE_SUFFIX)),
+            "rustc",
+        )
+        .unwrap();
+        let gcc = Gcc::new(
+            XTENSA_GCC,
+            &get_host_triple(None).unwrap(),
+            &toolchain_dir,
+            None,
+        );
+        create_dir_all(gcc.get_bin_path()).unwrap();
+        write(format!("{}/gcc", gcc.get_bin_path()), "gcc").unwrap();
+
+        let checks = diagnose(&config).unwrap();
+        let passed = |name: &str| {
+            checks
+                .iter()
+                .find(|check| check.name.starts_with(name))
+                .unwrap_or_else(|| panic!("missing check '{name}'"))
+                .passed
+        };
+        assert!(passed("Xtensa Rust 1.82.0.3 is installed"));
+        // Not linked to rustup, nor with LLVM
+        assert!(!passed("rustup lists the 'esp-doctor-test' toolchain"));
+        assert!(!passed("LLVM"));
+        assert!(passed(&format!("GCC ({XTENSA_GCC}) is installed")));
+        assert!(!passed("Export file"));
+
+        write(&export_file, "").unwrap();
+        assert!(diagnose(&config)
+            .unwrap()
+            .iter()
+            .any(|check| check.name.starts_with("Export file") && check.passed));
+    }
+}
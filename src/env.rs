@@ -1,12 +1,14 @@
 //! Environment variables set up and export file support.
 
+#[cfg(unix)]
+use crate::cli::ExportShell;
 use crate::error::Error;
 use directories::BaseDirs;
-use log::debug;
+use log::{debug, warn};
 use std::{
     env,
-    fs::File,
-    io::Write,
+    fs::{read_to_string, remove_file, File},
+    io::{stdout, Write},
     path::{Path, PathBuf},
 };
 #[cfg(windows)]
@@ -19,6 +21,8 @@ use winreg::{
 const DEFAULT_EXPORT_FILE: &str = "export-esp.ps1";
 #[cfg(not(windows))]
 const DEFAULT_EXPORT_FILE: &str = "export-esp.sh";
+/// First line of the export files generated by espup.
+const EXPORT_FILE_HEADER: &str = "# Generated by espup, changes will be overwritten";
 
 #[cfg(windows)]
 /// Sets an environment variable for the current user.
@@ -69,9 +73,19 @@ pub fn delete_env_variable(key: &str) -> Result<(), Error> {
     Ok(())
 }
 
+/// Returns whether the export file is `-`, to write it to stdout.
+pub fn is_stdout_export_file(export_file: &Path) -> bool {
+    export_file == Path::new("-")
+}
+
 /// Returns the absolute path to the export file, uses the DEFAULT_EXPORT_FILE if no arg is provided.
+///
+/// `-` is kept as is, to write the export file to stdout.
 pub fn get_export_file(export_file: Option<PathBuf>) -> Result<PathBuf, Error> {
     if let Some(export_file) = export_file {
+        if is_stdout_export_file(&export_file) {
+            return Ok(export_file);
+        }
         if export_file.is_dir() {
             return Err(Error::InvalidDestination(export_file.display().to_string()));
         }
@@ -83,16 +97,70 @@ pub fn get_export_file(export_file: Option<PathBuf>) -> Result<PathBuf, Error> {
         }
     } else {
         Ok(BaseDirs::new()
-            .unwrap()
+            .ok_or(Error::NoHomeDir)?
             .home_dir()
             .join(DEFAULT_EXPORT_FILE))
     }
 }
 
-/// Creates the export file with the necessary environment variables.
+/// Checks that an existing export file was generated by espup, so it can be overwritten.
+///
+/// Files generated by older versions of espup don't have a header, they are recognized by only
+/// containing exports.
+pub fn check_export_file(export_file: &Path, force: bool) -> Result<(), Error> {
+    if force || !export_file.is_file() {
+        return Ok(());
+    }
+    let content = read_to_string(export_file)?;
+    let is_espup_file = content.starts_with(EXPORT_FILE_HEADER)
+        || content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .all(|line| line.starts_with("export ") || line.starts_with("$Env:"));
+    if !is_espup_file {
+        return Err(Error::ForeignExportFile(export_file.display().to_string()));
+    }
+    Ok(())
+}
+
+/// Removes the export file and its versions for other shells, if they were generated by espup.
+///
+/// Files that were already removed are skipped.
+pub fn remove_export_files(export_file: &Path) -> Result<(), Error> {
+    let extensions = ["fish", "nu"];
+    let files = std::iter::once(export_file.to_path_buf()).chain(
+        extensions
+            .iter()
+            .map(|extension| export_file.with_extension(extension)),
+    );
+    for file in files {
+        if !file.is_file() {
+            continue;
+        }
+        if check_export_file(&file, false).is_err() {
+            warn!(
+                "Keeping '{}' as it was not generated by espup",
+                file.display()
+            );
+            continue;
+        }
+        debug!("Removing export file '{}'", file.display());
+        remove_file(&file)?;
+    }
+    Ok(())
+}
+
+/// Creates the export file with the necessary environment variables, writes it to stdout if it's
+/// `-`.
 pub fn create_export_file(export_file: &PathBuf, exports: &[String]) -> Result<(), Error> {
     debug!("Creating export file");
-    let mut file = File::create(export_file)?;
+    let mut file: Box<dyn Write> = if is_stdout_export_file(export_file) {
+        Box::new(stdout().lock())
+    } else {
+        Box::new(File::create(export_file)?)
+    };
+    file.write_all(EXPORT_FILE_HEADER.as_bytes())?;
+    file.write_all(b"\n")?;
     for e in exports.iter() {
         #[cfg(windows)]
         let e = e.replace('/', r"\");
@@ -103,6 +171,50 @@ pub fn create_export_file(export_file: &PathBuf, exports: &[String]) -> Result<(
     Ok(())
 }
 
+#[cfg(unix)]
+/// Returns whether the shell is the user's shell or has been configured for the user.
+pub fn is_shell_available(shell: ExportShell) -> bool {
+    let (version_var, name, config_dir) = match shell {
+        ExportShell::Fish => ("FISH_VERSION", "fish", ".config/fish"),
+        ExportShell::Nu => ("NU_VERSION", "nu", ".config/nushell"),
+    };
+    env::var_os(version_var).is_some()
+        || env::var("SHELL").is_ok_and(|shell| shell.ends_with(&format!("/{name}")))
+        || BaseDirs::new().is_some_and(|dirs| dirs.home_dir().join(config_dir).is_dir())
+}
+
+#[cfg(unix)]
+/// Converts an export of the export file into the syntax of the shell.
+fn to_shell_export(export: &str, shell: ExportShell) -> String {
+    let Some((key, value)) = export
+        .strip_prefix("export ")
+        .and_then(|export| export.split_once('='))
+    else {
+        return export.to_string();
+    };
+    match (value.strip_suffix(":$PATH\""), shell) {
+        (Some(path), ExportShell::Fish) if key == "PATH" => {
+            format!("set -gx PATH {path}\" $PATH")
+        }
+        (Some(path), ExportShell::Nu) if key == "PATH" => {
+            format!("$env.PATH = ($env.PATH | split row (char esep) | prepend {path}\")")
+        }
+        (_, ExportShell::Fish) => format!("set -gx {key} {value}"),
+        (_, ExportShell::Nu) => format!("$env.{key} = {value}"),
+    }
+}
+
+#[cfg(unix)]
+/// Creates the version of the export file for the shell.
+pub fn create_shell_export_file(
+    export_file: &PathBuf,
+    exports: &[String],
+    shell: ExportShell,
+) -> Result<(), Error> {
+    let exports: Vec<String> = exports.iter().map(|e| to_shell_export(e, shell)).collect();
+    create_export_file(export_file, &exports)
+}
+
 #[cfg(windows)]
 // Get the windows PATH variable out of the registry as a String.
 pub fn get_windows_path_var() -> Result<String, Error> {
@@ -154,9 +266,16 @@ pub fn set_env() -> Result<(), Error> {
 }
 
 /// Instructions to export the environment variables.
-pub fn print_post_install_msg(export_file: &Path) -> Result<(), Error> {
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn print_post_install_msg(export_file: &Path, no_modify_path: bool) -> Result<(), Error> {
     #[cfg(windows)]
-    if cfg!(windows) {
+    if no_modify_path {
+        println!(
+            "\n\tTo get started, you need to set up some environment variables by running: '. {}'",
+            export_file.display()
+        );
+        println!("\tThis step must be done every time you open a new terminal.");
+    } else {
         println!(
             "\n\tYour environments variables have been updated! Shell may need to be restarted for changes to be effective"
         );
@@ -182,11 +301,16 @@ pub fn print_post_install_msg(export_file: &Path) -> Result<(), Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::env::{create_export_file, get_export_file, DEFAULT_EXPORT_FILE};
+    use crate::env::{
+        check_export_file, create_export_file, get_export_file, remove_export_files,
+        DEFAULT_EXPORT_FILE, EXPORT_FILE_HEADER,
+    };
+    #[cfg(unix)]
+    use crate::{cli::ExportShell, env::create_shell_export_file};
     use directories::BaseDirs;
     use std::{
         env::current_dir,
-        fs::{create_dir_all, read_to_string},
+        fs::{create_dir_all, read_to_string, write},
         path::PathBuf,
     };
     use tempfile::TempDir;
@@ -226,7 +350,10 @@ mod tests {
         ];
         create_export_file(&export_file, &exports).unwrap();
         let contents = read_to_string(export_file).unwrap();
-        assert_eq!(contents, "export VAR1=value1\nexport VAR2=value2\n");
+        assert_eq!(
+            contents,
+            format!("{EXPORT_FILE_HEADER}\nexport VAR1=value1\nexport VAR2=value2\n")
+        );
 
         // Returns the correct error when it fails to create the export file (it already exists)
         let temp_dir = TempDir::new().unwrap();
@@ -238,4 +365,70 @@ mod tests {
         ];
         assert!(create_export_file(&export_file, &exports).is_err());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_shell_export_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let exports = vec![
+            "export LIBCLANG_PATH=\"/esp/lib\"".to_string(),
+            "export PATH=\"/esp/bin:$PATH\"".to_string(),
+        ];
+        let export_file = temp_dir.path().join("export.fish");
+        create_shell_export_file(&export_file, &exports, ExportShell::Fish).unwrap();
+        assert_eq!(
+            read_to_string(export_file).unwrap(),
+            format!(
+                "{EXPORT_FILE_HEADER}\nset -gx LIBCLANG_PATH \"/esp/lib\"\nset -gx PATH \"/esp/bin\" $PATH\n"
+            )
+        );
+        let export_file = temp_dir.path().join("export.nu");
+        create_shell_export_file(&export_file, &exports, ExportShell::Nu).unwrap();
+        assert_eq!(
+            read_to_string(export_file).unwrap(),
+            format!(
+                "{EXPORT_FILE_HEADER}\n$env.LIBCLANG_PATH = \"/esp/lib\"\n$env.PATH = ($env.PATH | split row (char esep) | prepend \"/esp/bin\")\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_remove_export_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let export_file = temp_dir.path().join("export-esp.sh");
+        let fish_export_file = temp_dir.path().join("export-esp.fish");
+        let nu_export_file = temp_dir.path().join("export-esp.nu");
+        create_export_file(&export_file, &["export VAR1=value1".to_string()]).unwrap();
+        create_export_file(&fish_export_file, &["set -gx VAR1 value1".to_string()]).unwrap();
+        write(&nu_export_file, "$env.config.show_banner = false\n").unwrap();
+
+        remove_export_files(&export_file).unwrap();
+        assert!(!export_file.exists());
+        assert!(!fish_export_file.exists());
+        assert!(nu_export_file.exists());
+        // Files already removed are skipped
+        assert!(remove_export_files(&export_file).is_ok());
+    }
+
+    #[test]
+    fn test_check_export_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let export_file = temp_dir.path().join("export.sh");
+        // Missing file
+        assert!(check_export_file(&export_file, false).is_ok());
+        // File generated by espup
+        create_export_file(&export_file, &["export VAR1=value1".to_string()]).unwrap();
+        assert!(check_export_file(&export_file, false).is_ok());
+        // File generated by an older espup, without header
+        write(&export_file, "export LIBCLANG_PATH=\"/path\"\n").unwrap();
+        assert!(check_export_file(&export_file, false).is_ok());
+        // Foreign file is preserved unless forced
+        write(&export_file, "alias ll='ls -l'\nexport VAR1=value1\n").unwrap();
+        assert!(check_export_file(&export_file, false).is_err());
+        assert!(check_export_file(&export_file, true).is_ok());
+        assert_eq!(
+            read_to_string(&export_file).unwrap(),
+            "alias ll='ls -l'\nexport VAR1=value1\n"
+        );
+    }
 }
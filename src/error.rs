@@ -2,10 +2,32 @@
 
 #[derive(Debug, miette::Diagnostic, thiserror::Error)]
 pub enum Error {
+    #[diagnostic(code(espup::aborted))]
+    #[error("Operation aborted by the user")]
+    Aborted,
+
+    #[diagnostic(code(espup::toolchain::checksum_mismatch))]
+    #[error("Checksum verification of '{0}' failed")]
+    ChecksumMismatch(String),
+
     #[diagnostic(code(espup::toolchain::create_directory))]
     #[error("Creating directory '{0}' failed")]
     CreateDirectory(String),
 
+    #[diagnostic(code(espup::toolchain::rust::dangling_toolchain_link))]
+    #[error(
+        "Toolchain '{0}' is a link to a directory that no longer exists. Please, remove it manually and try again")]
+    DanglingToolchainLink(String),
+
+    #[diagnostic(code(espup::doctor::failed_checks))]
+    #[error("{0} check(s) of the installation failed. Please, run `espup reinstall` to repair it")]
+    FailedChecks(usize),
+
+    #[diagnostic(code(espup::foreign_export_file))]
+    #[error(
+        "Export file '{0}' was not generated by espup. Please, use `--force` to overwrite it or set another path with `--export-file`")]
+    ForeignExportFile(String),
+
     #[diagnostic(code(espup::toolchain::rust::query_github))]
     #[error("Failed to query GitHub API: Rate Limiting")]
     GithubRateLimit,
@@ -16,17 +38,49 @@ pub enum Error {
 
     #[diagnostic(code(espup::toolchain::http_error))]
     #[error("HTTP GET Error: {0}")]
-    HttpError(String),
+    HttpError(reqwest::StatusCode),
+
+    #[diagnostic(code(espup::backup::incompatible_backup))]
+    #[error("Backup was created for '{0}' and cannot be restored on '{1}'")]
+    IncompatibleBackup(String, String),
 
     #[diagnostic(code(espup::toolchain::rust::install_riscv_target))]
     #[error("Failed to Install RISC-V targets for '{0}' toolchain")]
     InstallRiscvTarget(String),
 
+    #[diagnostic(
+        code(espup::toolchain::insufficient_disk_space),
+        help("Free some disk space or use `--force` to install anyway")
+    )]
+    #[error("The installation requires about {0} but only {1} are available")]
+    InsufficientDiskSpace(String, String),
+
+    #[diagnostic(code(espup::backup::invalid_backup))]
+    #[error("Invalid backup file '{0}'")]
+    InvalidBackup(String),
+
+    #[diagnostic(code(espup::targets::invalid_cargo_metadata))]
+    #[error("Failed to read targets from '{0}': {1}")]
+    InvalidCargoMetadata(String, String),
+
+    #[diagnostic(code(espup::config::invalid_config))]
+    #[error("Invalid config file '{0}'. Please, remove it and run `espup install` again")]
+    InvalidConfig(String),
+
     #[diagnostic(code(espup::ivalid_destination))]
     #[error(
         "Invalid export file destination: '{0}'. Please, use an absolute or releative path (including the file and its extension)")]
     InvalidDestination(String),
 
+    #[diagnostic(code(espup::profile::invalid_install_profile))]
+    #[error("Invalid install profile '{0}': {1}")]
+    InvalidInstallProfile(String, String),
+
+    #[diagnostic(code(espup::toolchain::invalid_release_url))]
+    #[error(
+        "Invalid release URL '{0}'. Please, use a GitHub release page (`https://github.com/<owner>/<repo>/releases/tag/<tag>`) or API URL")]
+    InvalidReleaseUrl(String),
+
     #[diagnostic(code(espup::toolchain::rust::invalid_version))]
     #[error(
         "Invalid toolchain version '{0}'. Verify that the format is correct: '<major>.<minor>.<patch>.<subpatch>' or '<major>.<minor>.<patch>', and that the release exists in https://github.com/esp-rs/rust-build/releases")]
@@ -35,14 +89,64 @@ pub enum Error {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 
+    #[diagnostic(
+        code(espup::toolchain::license_not_accepted),
+        help("Run with `--accept-license` to accept them without prompting")
+    )]
+    #[error("The licenses of the components to install must be accepted")]
+    LicenseNotAccepted,
+
+    #[diagnostic(code(espup::toolchain::rust::link_toolchain))]
+    #[error("Failed to link '{0}' toolchain to rustup")]
+    LinkToolchain(String),
+
+    #[diagnostic(code(espup::toolchain::missing_artifacts))]
+    #[error("{} artifact(s) are not available for the selected host and versions:\n  - {}", .0.len(), .0.join("\n  - "))]
+    MissingArtifacts(Vec<String>),
+
+    #[diagnostic(code(espup::toolchain::missing_bundle_file))]
+    #[error("File '{0}' is missing from the bundle. Please, create the bundle with `espup download` using the same options")]
+    MissingBundleFile(String),
+
+    #[diagnostic(code(espup::config::missing_config))]
+    #[error("Toolchain '{0}' was not installed by this version of espup. Please, run `espup install` first")]
+    MissingConfig(String),
+
+    #[diagnostic(code(espup::toolchain::missing_release_asset))]
+    #[error("No {0} asset found in release '{1}'")]
+    MissingReleaseAsset(String, String),
+
     #[diagnostic(code(espup::toolchain::rust::missing_rust))]
     #[error("Rust is not installed. Please, install Rust via rustup: https://rustup.rs/")]
     MissingRust,
 
+    #[diagnostic(code(espup::toolchain::rust::missing_rust_src))]
+    #[error("Failed to add the 'rust-src' component to '{0}' toolchain, required to build with `build-std`")]
+    MissingRustSrc(String),
+
+    #[diagnostic(code(espup::missing_toolchain))]
+    #[error("Toolchain '{0}' is not installed. Please, run `espup install` first")]
+    MissingToolchain(String),
+
+    #[diagnostic(
+        code(espup::no_home_dir),
+        help("Set `HOME`, or `ESPUP_CONFIG_HOME`, `CARGO_HOME`, `RUSTUP_HOME` and `--export-file` to the directories to use instead")
+    )]
+    #[error("Failed to find the home directory")]
+    NoHomeDir,
+
+    #[diagnostic(code(espup::no_targets_left))]
+    #[error("Removing the targets would leave '{0}' without targets. Please, run `espup uninstall` instead")]
+    NoTargetsLeft(String),
+
     #[diagnostic(code(espup::remove_directory))]
     #[error("Failed to remove '{0}'")]
     RemoveDirectory(String),
 
+    #[diagnostic(code(espup::backup::restore_destination_exists))]
+    #[error("Toolchain '{0}' already exists. Please, uninstall it before restoring a backup")]
+    RestoreDestinationExists(String),
+
     #[error(transparent)]
     RewquestError(#[from] reqwest::Error),
 
@@ -50,26 +154,54 @@ pub enum Error {
     #[error("Error detecting rustup: {0}")]
     RustupDetection(String),
 
+    #[diagnostic(code(espup::toolchain::rust::rustup_update))]
+    #[error("Failed to update '{0}' toolchain with rustup")]
+    RustupUpdate(String),
+
+    #[diagnostic(code(espup::self_test::self_test))]
+    #[error("Failed to build a test crate for '{0}': {1}")]
+    SelfTest(String, String),
+
     #[diagnostic(code(espup::toolchain::rust::serialize_json))]
     #[error("Failed to serialize json from string")]
     SerializeJson,
 
+    #[diagnostic(code(espup::toolchain::rust::set_default_toolchain))]
+    #[error("Failed to set '{0}' as the default toolchain")]
+    SetDefaultToolchain(String),
+
     #[diagnostic(code(espup::toolchain::rust::uninstall_riscv_target))]
     #[error("Failed to uninstall RISC-V target")]
     UninstallRiscvTarget,
 
+    #[diagnostic(code(espup::info::unknown_placeholder))]
+    #[error("Unknown placeholder '{{{0}}}' in format template. Valid placeholders are: {1}")]
+    UnknownPlaceholder(String, String),
+
     #[diagnostic(code(espup::toolchain::unsupported_file_extension))]
     #[error("Unsuported file extension: '{0}'")]
     UnsuportedFileExtension(String),
 
+    #[diagnostic(code(espup::toolchain::gcc::unsupported_gcc_version))]
+    #[error("GCC version '{0}' is not a release of https://github.com/espressif/crosstool-NG")]
+    UnsupportedGccVersion(String),
+
     #[diagnostic(code(espup::host_triple::unsupported_host_triple))]
-    #[error("Host triple '{0}' is not supported")]
-    UnsupportedHostTriple(String),
+    #[error("Host triple '{0}' is not supported, the supported ones are: {1}")]
+    UnsupportedHostTriple(String, String),
+
+    #[diagnostic(code(espup::toolchain::llvm::unsupported_llvm_version))]
+    #[error("LLVM version '{0}' is not supported, use one of {1}")]
+    UnsupportedLlvmVersion(String, String),
 
     #[diagnostic(code(espup::targets::unsupported_target))]
     #[error("Target '{0}' is not supported")]
     UnsupportedTarget(String),
 
+    #[diagnostic(code(espup::warnings))]
+    #[error("{} warning(s) emitted while using `--fail-on-warning`:\n  - {}", .0.len(), .0.join("\n  - "))]
+    Warnings(Vec<String>),
+
     #[diagnostic(code(espup::toolchain::rust::rust))]
     #[error("Failed to install 'rust' component of Xtensa Rust")]
     XtensaRust,
@@ -4,9 +4,9 @@ use crate::error::Error;
 use guess_host_triple::guess_host_triple;
 use miette::Result;
 use std::str::FromStr;
-use strum::{Display, EnumString};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Display, Debug, Clone, EnumString, Default)]
+#[derive(Display, Debug, Clone, EnumIter, EnumString, Default)]
 pub enum HostTriple {
     /// 64-bit Linux
     #[strum(serialize = "x86_64-unknown-linux-gnu")]
@@ -37,7 +37,12 @@ pub fn get_host_triple(host_triple_arg: Option<String>) -> Result<HostTriple, Er
         guess_host_triple().unwrap()
     };
 
-    HostTriple::from_str(host_triple).map_err(|_| Error::UnsupportedHostTriple(host_triple.into()))
+    HostTriple::from_str(host_triple).map_err(|_| {
+        let supported: Vec<String> = HostTriple::iter()
+            .map(|triple| triple.to_string())
+            .collect();
+        Error::UnsupportedHostTriple(host_triple.into(), supported.join(", "))
+    })
 }
 
 #[cfg(test)]
@@ -71,7 +76,9 @@ mod tests {
             Ok(HostTriple::Aarch64AppleDarwin)
         ));
 
-        assert!(get_host_triple(Some("some-fake-triple".to_string())).is_err());
+        let error = get_host_triple(Some("some-fake-triple".to_string())).unwrap_err();
+        assert!(error.to_string().contains("some-fake-triple"));
+        assert!(error.to_string().contains("aarch64-apple-darwin"));
 
         // Guessed Host Triples
         #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
@@ -0,0 +1,145 @@
+//! Build metadata and default components information.
+
+use crate::{
+    error::Error,
+    host_triple::get_host_triple,
+    toolchain::{
+        gcc::DEFAULT_GCC_RELEASE,
+        llvm::Llvm,
+        rust::{get_rustup_toolchain_dir, XtensaRust},
+    },
+};
+use log::warn;
+use std::time::Duration;
+
+/// Information about espup build and the components it would install.
+#[derive(Debug, Clone)]
+pub struct Info {
+    /// Fields as `(name, value)` pairs, in display order.
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl Info {
+    /// Gathers the build metadata and resolves the default component versions, for a toolchain
+    /// named `name`. The latest Xtensa Rust version is cached as in the installations.
+    pub async fn new(
+        name: &str,
+        version_cache_ttl: Duration,
+        refresh: bool,
+    ) -> Result<Self, Error> {
+        let host_triple = get_host_triple(None)?;
+        let toolchain_path = get_rustup_toolchain_dir(name)?;
+        let xtensa_rust_version =
+            XtensaRust::get_cached_latest_version(version_cache_ttl, refresh, true)
+                .await
+                .map_err(|e| warn!("Failed to resolve the latest Xtensa Rust version: {e}"))
+                .ok();
+        let llvm = xtensa_rust_version.as_ref().and_then(|version| {
+            Llvm::new(&toolchain_path, &host_triple, false, version, None).ok()
+        });
+
+        let unknown = || "unknown".to_string();
+        Ok(Self {
+            fields: vec![
+                ("espup_version", env!("CARGO_PKG_VERSION").to_string()),
+                ("git_commit", env!("ESPUP_GIT_COMMIT").to_string()),
+                ("build_date", env!("ESPUP_BUILD_DATE").to_string()),
+                ("host_triple", host_triple.to_string()),
+                (
+                    "xtensa_rust_version",
+                    xtensa_rust_version.unwrap_or_else(unknown),
+                ),
+                (
+                    "llvm_version",
+                    llvm.as_ref()
+                        .map(|llvm| llvm.version.clone())
+                        .unwrap_or_else(unknown),
+                ),
+                (
+                    "llvm_path",
+                    llvm.map(|llvm| llvm.path.display().to_string())
+                        .unwrap_or_else(unknown),
+                ),
+                ("gcc_version", DEFAULT_GCC_RELEASE.to_string()),
+                ("toolchain_path", toolchain_path.display().to_string()),
+            ],
+        })
+    }
+
+    /// Returns the information as human readable text.
+    pub fn to_text(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns the information interpolated into a template with `{field}` placeholders. An
+    /// unclosed `{` is kept as is.
+    pub fn format(&self, template: &str) -> Result<String, Error> {
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            output.push_str(&rest[..start]);
+            let name = &rest[start + 1..start + end];
+            let value = self
+                .fields
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| value)
+                .ok_or_else(|| {
+                    Error::UnknownPlaceholder(
+                        name.to_string(),
+                        self.fields
+                            .iter()
+                            .map(|(field, _)| format!("{{{field}}}"))
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                    )
+                })?;
+            output.push_str(value);
+            rest = &rest[start + end + 1..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// Returns the information as a JSON object.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), serde_json::Value::from(value.clone())))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::info::Info;
+
+    #[test]
+    fn test_info_format() {
+        let info = Info {
+            fields: vec![
+                ("espup_version", "0.14.1".to_string()),
+                ("llvm_path", "/tmp/esp/llvm".to_string()),
+            ],
+        };
+        assert_eq!(
+            info.format("{espup_version} {llvm_path}").unwrap(),
+            "0.14.1 /tmp/esp/llvm"
+        );
+        assert_eq!(info.format("no fields").unwrap(), "no fields");
+        assert!(info.format("{gcc_path}").is_err());
+        assert_eq!(
+            info.format("{espup_version} {llvm_path").unwrap(),
+            "0.14.1 {llvm_path"
+        );
+    }
+}
@@ -1,18 +1,128 @@
+pub mod backup;
 pub mod cli;
+pub mod config;
+pub mod doctor;
 pub mod env;
 pub mod error;
 pub mod host_triple;
+pub mod info;
+pub mod profile;
+pub mod progress;
+pub mod self_test;
 pub mod targets;
 pub mod toolchain;
 
+pub use crate::toolchain::{InstallOutcome, UninstallOutcome};
+use crate::{
+    cli::{InstallOpts, UninstallOpts},
+    error::Error,
+    toolchain::InstallMode,
+};
+
+/// Installs the Espressif Rust ecosystem.
+///
+/// Returns `None` when nothing was installed, with `--dry-run` or `--download-only`. Nothing is
+/// asked to the user, unless enabled with [`toolchain::set_prompts_enabled`]: set `accept_license`
+/// and `yes` in `args` to go on where espup would ask.
+pub async fn install(args: InstallOpts) -> Result<Option<InstallOutcome>, Error> {
+    toolchain::install(args, InstallMode::Install).await
+}
+
+/// Updates the Espressif Rust ecosystem.
+///
+/// Returns `None` when nothing was installed, with `--dry-run` or `--download-only`. Prompts are
+/// handled as in [`install`].
+pub async fn update(args: InstallOpts) -> Result<Option<InstallOutcome>, Error> {
+    toolchain::install(args, InstallMode::Update).await
+}
+
+/// Uninstalls the Espressif Rust ecosystem.
+pub async fn uninstall(args: UninstallOpts) -> Result<UninstallOutcome, Error> {
+    toolchain::uninstall(args).await
+}
+
 pub mod logging {
-    use env_logger::{Builder, Env, WriteStyle};
+    use env_logger::{Builder, Env, Target, WriteStyle};
+    use log::{warn, Level, LevelFilter, Log, Metadata, Record};
+    use std::{fs::File, path::Path, sync::Mutex};
 
     use crate::toolchain::PROCESS_BARS;
 
+    lazy_static::lazy_static! {
+        static ref WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    }
+
+    /// Logger that keeps track of the warnings emitted by espup, even the filtered ones.
+    struct Logger {
+        inner: env_logger::Logger,
+        /// Logger of the `--log-file`, if any.
+        file: Option<env_logger::Logger>,
+    }
+
+    impl Log for Logger {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= Level::Warn
+                || self.inner.enabled(metadata)
+                || self
+                    .file
+                    .as_ref()
+                    .is_some_and(|file| file.enabled(metadata))
+        }
+
+        fn log(&self, record: &Record) {
+            if record.level() == Level::Warn && record.target().starts_with("espup") {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+            self.inner.log(record);
+            if let Some(file) = &self.file {
+                file.log(record);
+            }
+        }
+
+        fn flush(&self) {
+            self.inner.flush();
+            if let Some(file) = &self.file {
+                file.flush();
+            }
+        }
+    }
+
+    /// Builds the logger writing the debug logs of espup to a file, without colors and with
+    /// timestamps.
+    fn file_logger(file: File) -> env_logger::Logger {
+        Builder::new()
+            .filter_level(LevelFilter::Warn)
+            .filter_module("espup", LevelFilter::Debug)
+            .format(|buf, record| {
+                use std::io::Write;
+                writeln!(
+                    buf,
+                    "{} [{}]: {}",
+                    buf.timestamp(),
+                    record.level().to_string().to_lowercase(),
+                    record.args()
+                )
+            })
+            .write_style(WriteStyle::Never)
+            .target(Target::Pipe(Box::new(file)))
+            .build()
+    }
+
     /// Initializes the logger
-    pub fn initialize_logger(log_level: &str) {
-        let logger = Builder::from_env(Env::default().default_filter_or(log_level))
+    ///
+    /// If `log_module` is provided, it's parsed as a per-module filter (same syntax as `RUST_LOG`)
+    /// and takes precedence over `log_level`. If `log_file` is provided, the logs are also
+    /// written to it, including the debug ones.
+    pub fn initialize_logger(log_level: &str, log_module: Option<&str>, log_file: Option<&Path>) {
+        let mut builder = match log_module {
+            Some(filters) => {
+                let mut builder = Builder::new();
+                builder.parse_filters(filters);
+                builder
+            }
+            None => Builder::from_env(Env::default().default_filter_or(log_level)),
+        };
+        let logger = builder
             .format(|buf, record| {
                 use std::io::Write;
                 writeln!(
@@ -24,20 +134,65 @@ pub mod logging {
             })
             .write_style(WriteStyle::Always)
             .build();
-        let level = logger.filter();
+        let mut level = logger.filter();
+        let mut file_error = None;
+        let file_logger = log_file.and_then(|log_file| match File::create(log_file) {
+            Ok(file) => Some(file_logger(file)),
+            Err(e) => {
+                file_error = Some(format!(
+                    "Failed to create log file '{}': {}",
+                    log_file.display(),
+                    e
+                ));
+                None
+            }
+        });
+        if let Some(file_logger) = &file_logger {
+            level = level.max(file_logger.filter());
+        }
         // make logging and process bar no longer mixed up
-        indicatif_log_bridge::LogWrapper::new(PROCESS_BARS.clone(), logger)
-            .try_init()
-            .unwrap();
-        log::set_max_level(level);
+        indicatif_log_bridge::LogWrapper::new(
+            PROCESS_BARS.clone(),
+            Logger {
+                inner: logger,
+                file: file_logger,
+            },
+        )
+        .try_init()
+        .unwrap();
+        log::set_max_level(level.max(LevelFilter::Warn));
+        if let Some(file_error) = file_error {
+            warn!("{}", file_error);
+        }
+    }
+
+    /// Returns the warnings emitted so far.
+    pub fn warnings() -> Vec<String> {
+        WARNINGS.lock().unwrap().clone()
     }
 }
 
 pub mod update {
-    use log::warn;
-    use std::time::Duration;
+    use crate::{
+        error::Error,
+        host_triple::get_host_triple,
+        toolchain::{download_file, github_query},
+    };
+    use log::{debug, info, warn};
+    use std::{
+        env,
+        fs::{remove_file, rename},
+        path::Path,
+        time::Duration,
+    };
+    use tempfile::tempdir_in;
     use update_informer::{registry, Check};
 
+    /// espup releases repository
+    const ESPUP_RELEASES_URL: &str = "https://github.com/esp-rs/espup/releases/download";
+    /// espup latest release API URL
+    const ESPUP_LATEST_API_URL: &str = "https://api.github.com/repos/esp-rs/espup/releases/latest";
+
     /// Check crates.io for a new version of the application
     pub fn check_for_update(name: &str, version: &str) {
         // By setting the interval to 0 seconds we invalidate the cache with each
@@ -49,4 +204,69 @@ pub mod update {
             warn!("A new version of {name} ('{version}') is available");
         }
     }
+
+    /// Replaces the running espup binary with the requested GitHub release, or the latest one.
+    pub async fn self_update(version: Option<String>) -> Result<(), Error> {
+        let version = if let Some(version) = version {
+            version.trim_start_matches('v').to_string()
+        } else {
+            let json = tokio::task::spawn_blocking(|| github_query(ESPUP_LATEST_API_URL))
+                .await
+                .unwrap()?;
+            json["tag_name"].to_string().replace(['"', 'v'], "")
+        };
+        if version == env!("CARGO_PKG_VERSION") {
+            info!("espup {version} is already installed");
+            return Ok(());
+        }
+
+        info!("Updating espup to version {version}");
+        let host_triple = get_host_triple(None)?;
+        let asset = format!("espup-{host_triple}{}", env::consts::EXE_SUFFIX);
+        let current_exe = env::current_exe()?;
+        // Download next to the current binary so the final rename doesn't cross filesystems
+        let tmp_dir = tempdir_in(current_exe.parent().unwrap())?;
+        let tmp_dir_path = &tmp_dir.path().display().to_string();
+
+        let binary = download_file(
+            format!("{ESPUP_RELEASES_URL}/v{version}/{asset}"),
+            &asset,
+            tmp_dir_path,
+            false,
+            false,
+            true,
+        )
+        .await?;
+
+        replace_executable(Path::new(&binary), &current_exe)?;
+        info!("espup successfully updated to version {version}");
+        Ok(())
+    }
+
+    /// Moves the new binary into the location of the running executable.
+    fn replace_executable(new_exe: &Path, current_exe: &Path) -> Result<(), Error> {
+        debug!(
+            "Replacing '{}' with '{}'",
+            current_exe.display(),
+            new_exe.display()
+        );
+        #[cfg(unix)]
+        {
+            use std::{fs::set_permissions, os::unix::fs::PermissionsExt};
+            set_permissions(new_exe, PermissionsExt::from_mode(0o755))?;
+        }
+        // Windows doesn't allow overwriting a running executable, but it allows renaming it
+        let old_exe = current_exe.with_extension("old");
+        if old_exe.exists() {
+            remove_file(&old_exe)?;
+        }
+        rename(current_exe, &old_exe)?;
+        if let Err(e) = rename(new_exe, current_exe) {
+            rename(&old_exe, current_exe)?;
+            return Err(e.into());
+        }
+        #[cfg(unix)]
+        remove_file(&old_exe)?;
+        Ok(())
+    }
 }
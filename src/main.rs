@@ -1,20 +1,37 @@
-use clap::{CommandFactory, Parser};
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser};
 use espup::{
-    cli::{CompletionsOpts, InstallOpts, UninstallOpts},
-    logging::initialize_logger,
+    backup::{backup, restore},
+    cli::{
+        BackupOpts, CompletionsOpts, DoctorOpts, DownloadOpts, ExportOnlyOpts, InfoOpts,
+        InstallOpts, ListOpts, OutputFormat, ReinstallOpts, RestoreOpts, ShowConfigOpts,
+        TargetOpts, UninstallOpts, UpdateOpts,
+    },
+    config::Config,
+    doctor::diagnose,
+    env::{create_export_file, get_export_file, is_stdout_export_file, print_post_install_msg},
+    error::Error,
+    info::Info,
+    logging::{initialize_logger, warnings},
+    profile::InstallProfile,
+    targets::{get_targets_change, TargetsChange},
     toolchain::{
-        gcc::uninstall_gcc_toolchains,
+        confirm, get_config_exports, get_config_paths, get_installed_components,
         install as toolchain_install,
         llvm::Llvm,
-        remove_dir,
-        rust::{get_rustup_home, XtensaRust},
-        InstallMode,
+        rust::{
+            get_default_toolchain, get_espup_dir, get_rustup_toolchain_dir, link_toolchain,
+            remove_dangling_toolchain_link, remove_toolchain_link, set_default_toolchain,
+            XtensaRust,
+        },
+        set_prompts_enabled, uninstall_toolchain, InstallMode,
     },
-    update::check_for_update,
+    update::{check_for_update, self_update},
 };
-use log::info;
+#[cfg(unix)]
+use espup::{cli::ExportShell, env::create_shell_export_file};
+use log::{info, warn};
 use miette::Result;
-use std::{env, io::stdout};
+use std::{env, io::stdout, time::Duration};
 
 #[derive(Parser)]
 #[command(about, version)]
@@ -25,20 +42,77 @@ struct Cli {
 
 #[derive(Parser)]
 pub enum SubCommand {
+    /// Adds targets to an installation, only installing the components they need.
+    AddTarget(TargetOpts),
+    /// Archives the installation into a backup file.
+    Backup(BackupOpts),
     /// Generate completions for the given shell.
     Completions(CompletionsOpts),
+    /// Checks that the components of an installation are in place.
+    Doctor(DoctorOpts),
+    /// Downloads the artifacts of the components to install into a bundle for offline installations.
+    // We use a Box here to make clippy happy (see https://rust-lang.github.io/rust-clippy/master/index.html#large_enum_variant)
+    Download(Box<DownloadOpts>),
+    /// Regenerates the export file of an installation, without downloading anything.
+    ExportOnly(ExportOnlyOpts),
+    /// Prints build information and the default versions of the components to install.
+    Info(InfoOpts),
     /// Installs Espressif Rust ecosystem.
     // We use a Box here to make clippy happy (see https://rust-lang.github.io/rust-clippy/master/index.html#large_enum_variant)
     Install(Box<InstallOpts>),
+    /// Lists the installed components.
+    List(ListOpts),
+    /// Reinstalls Espressif Rust ecosystem with the same options it was installed with.
+    Reinstall(ReinstallOpts),
+    /// Removes targets from an installation, along with the components no longer needed.
+    RemoveTarget(TargetOpts),
+    /// Restores an installation from a backup file.
+    Restore(RestoreOpts),
+    /// Prints the config of an installation as JSON.
+    ShowConfig(ShowConfigOpts),
     /// Uninstalls Espressif Rust ecosystem.
     Uninstall(UninstallOpts),
     /// Updates Xtensa Rust toolchain.
-    Update(Box<InstallOpts>),
+    Update(Box<UpdateOpts>),
+}
+
+/// Archives the installation into a backup file
+async fn backup_installation(args: BackupOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?;
+    let toolchain_dir = match &config {
+        Some(config) => config.toolchain_dir()?,
+        None => get_rustup_toolchain_dir(&args.name)?,
+    };
+    if !toolchain_dir.exists() {
+        return Err(Error::MissingToolchain(toolchain_dir.display().to_string()).into());
+    }
+    let export_file = get_export_file(
+        args.export_file
+            .or_else(|| config.map(|config| config.export_file)),
+    )?;
+    backup(
+        &args.file,
+        &toolchain_dir,
+        &export_file,
+        &get_espup_dir()?.join("esp-clang"),
+        Some(&Config::get_path(&args.name, args.config_path.as_deref())?),
+    )?;
+    Ok(())
 }
 
 /// Updates Xtensa Rust toolchain.
 async fn completions(args: CompletionsOpts) -> Result<()> {
-    initialize_logger(&args.log_level);
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
     check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
     info!("Generating completions for {} shell", args.shell);
@@ -50,43 +124,408 @@ async fn completions(args: CompletionsOpts) -> Result<()> {
     Ok(())
 }
 
+/// Downloads the artifacts of the components into a bundle for offline installations
+async fn download(args: DownloadOpts) -> Result<()> {
+    install(args.into(), InstallMode::Install).await
+}
+
+/// Checks that the components of an installation are in place
+async fn doctor(args: DoctorOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?
+        .ok_or(Error::MissingConfig(args.name))?;
+    config.set_home_vars();
+    let checks = diagnose(&config)?;
+    for check in &checks {
+        let status = if check.passed { "ok" } else { "failed" };
+        println!("[{status}] {}", check.name);
+    }
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        return Err(Error::FailedChecks(failed).into());
+    }
+    info!("All checks passed!");
+    Ok(())
+}
+
+/// Prints build information and the default versions of the components
+async fn info(args: InfoOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let info = Info::new(
+        &args.name,
+        Duration::from_secs(args.version_cache_ttl.saturating_mul(60 * 60)),
+        args.refresh,
+    )
+    .await?;
+    if let Some(template) = &args.format {
+        println!("{}", info.format(template)?);
+        return Ok(());
+    }
+    match args.output {
+        OutputFormat::Human => println!("{}", info.to_text()),
+        OutputFormat::Json => println!("{:#}", info.to_json()),
+    }
+    Ok(())
+}
+
+/// Lists the installed components
+async fn list(args: ListOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?;
+    if let Some(config) = &config {
+        config.set_home_vars();
+    }
+    let toolchain_link = get_rustup_toolchain_dir(&args.name)?;
+    remove_dangling_toolchain_link(&toolchain_link)?;
+    let toolchain_dir = match config {
+        Some(config) => config.toolchain_dir()?,
+        None => toolchain_link,
+    };
+    if !toolchain_dir.exists() {
+        info!(
+            "Toolchain '{}' is not installed, run `espup install` first",
+            args.name
+        );
+        return Ok(());
+    }
+
+    println!("Toolchain '{}' ({}):", args.name, toolchain_dir.display());
+    for (component, version) in get_installed_components(&toolchain_dir)? {
+        println!("  - {component}: {version}");
+    }
+    Ok(())
+}
+
+/// Reinstalls the Rust for ESP chips environment with the options of its installation
+async fn reinstall(args: ReinstallOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+    check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?
+        .ok_or(Error::MissingConfig(args.name))?;
+    let mut install_args = config.install_opts();
+    install_args.config_path = args.config_path;
+    install_args.log_level = args.log_level;
+    install_args.log_module = args.log_module;
+    install_args.log_file = args.log_file;
+    install_args.quiet = args.quiet;
+    if let Some(toolchain_version) = args.toolchain_version {
+        if toolchain_version != config.xtensa_rust_version {
+            install_args.xtensa_release_url = None;
+        }
+        install_args.toolchain_version = Some(toolchain_version);
+    }
+
+    info!("Reinstalling the Espressif Rust ecosystem");
+    let toolchain_dir = config.toolchain_dir()?;
+    remove_dangling_toolchain_link(&toolchain_dir)?;
+    if toolchain_dir.exists() {
+        uninstall_toolchain(&toolchain_dir, !config.no_modify_path).await?;
+    }
+    install_and_report(install_args, InstallMode::Install).await
+}
+
+/// Adds or removes targets of an installation, updating its config and export file
+async fn change_targets(args: TargetOpts, add: bool) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?
+        .ok_or(Error::MissingConfig(args.name))?;
+    let TargetsChange {
+        targets,
+        drops_xtensa,
+    } = get_targets_change(&config.targets, &args.targets, add);
+    if targets.is_empty() {
+        return Err(Error::NoTargetsLeft(config.name).into());
+    }
+
+    let toolchain_dir = config.toolchain_dir()?;
+    if drops_xtensa && toolchain_dir.exists() {
+        info!("Xtensa Rust and LLVM are no longer needed by the remaining targets");
+        if args.yes || confirm("Do you want to remove them?")? {
+            Llvm::uninstall(&toolchain_dir, !config.no_modify_path).await?;
+            XtensaRust::uninstall(&toolchain_dir).await?;
+            // The toolchain directory only keeps the GCC toolchains, rustup can't use it anymore
+            let toolchain_link = get_rustup_toolchain_dir(&config.name)?;
+            if toolchain_link != toolchain_dir {
+                remove_toolchain_link(&toolchain_link)?;
+            }
+            if get_default_toolchain().as_deref() == Some(config.name.as_str()) {
+                match &config.previous_default_toolchain {
+                    Some(previous_default_toolchain) => {
+                        set_default_toolchain(previous_default_toolchain)?
+                    }
+                    None => warn!(
+                        "Toolchain '{}' is still the default rustup toolchain but has no Rust compiler anymore, set another one with `rustup default`",
+                        config.name
+                    ),
+                }
+            }
+        }
+    }
+
+    let mut install_args = config.install_opts();
+    install_args.config_path = args.config_path;
+    install_args.log_level = args.log_level;
+    install_args.log_module = args.log_module;
+    install_args.log_file = args.log_file;
+    install_args.quiet = args.quiet;
+    install_args.targets = targets;
+    install_args.sync_targets = !add;
+    install_args.yes = args.yes;
+    install_and_report(install_args, InstallMode::Update).await
+}
+
+/// Restores an installation from a backup file
+async fn restore_installation(args: RestoreOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let install_path = args
+        .install_path
+        .map(|install_path| env::current_dir().map(|dir| dir.join(install_path)))
+        .transpose()
+        .map_err(Error::from)?;
+    let toolchain_dir = restore(
+        &args.file,
+        install_path.as_deref(),
+        &get_export_file(args.export_file)?,
+        &get_espup_dir()?.join("esp-clang"),
+        args.config_path.as_deref(),
+    )?;
+    if let (Some(name), true) = (
+        toolchain_dir.file_name().and_then(|name| name.to_str()),
+        install_path.is_some() && toolchain_dir.join("bin").exists(),
+    ) {
+        link_toolchain(name, &toolchain_dir)?;
+    }
+    info!("Toolchain restored in '{}'", toolchain_dir.display());
+    Ok(())
+}
+
+/// Prints the config of an installation as JSON, listing its paths that no longer exist
+async fn show_config(args: ShowConfigOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?
+        .ok_or(Error::MissingConfig(args.name))?;
+    let mut missing_paths: Vec<_> = get_config_paths(&config)?
+        .into_iter()
+        .filter(|path| !path.exists())
+        .collect();
+    if !is_stdout_export_file(&config.export_file) && !config.export_file.exists() {
+        missing_paths.push(config.export_file.clone());
+    }
+    let mut output = serde_json::json!(config);
+    output["missing_paths"] = serde_json::json!(missing_paths);
+    println!("{output:#}");
+    Ok(())
+}
+
+/// Returns the log level to use, only errors are logged when `quiet` is set
+fn get_log_level(log_level: &str, verbose: u8, quiet: bool) -> &str {
+    match (quiet, verbose) {
+        (true, _) => "error",
+        (false, 0) => log_level,
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    }
+}
+
 /// Installs or updates the Rust for ESP chips environment
 async fn install(args: InstallOpts, install_mode: InstallMode) -> Result<()> {
-    initialize_logger(&args.log_level);
+    initialize_logger(
+        get_log_level(
+            &args.log_level,
+            args.verbose,
+            args.quiet || args.output == OutputFormat::Json,
+        ),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
     check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
-    toolchain_install(args, install_mode).await?;
+    install_and_report(args, install_mode).await
+}
+
+/// Installs or updates the Rust for ESP chips environment, printing its outcome
+async fn install_and_report(args: InstallOpts, install_mode: InstallMode) -> Result<()> {
+    let fail_on_warning = args.fail_on_warning;
+    let output = args.output;
+    let quiet = args.quiet;
+    let no_modify_path = args.no_modify_path;
+    if let Some(outcome) = toolchain_install(args, install_mode).await? {
+        if output == OutputFormat::Json {
+            println!("{:#}", serde_json::json!(outcome));
+        // Quiet runs and export files written to stdout skip the post-install message
+        } else if !quiet && !is_stdout_export_file(&outcome.export_file) {
+            print_post_install_msg(&outcome.export_file, no_modify_path)?;
+            for shell_export_file in &outcome.shell_export_files {
+                println!(
+                    "\tOn {}, run 'source {}' instead",
+                    shell_export_file
+                        .extension()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                    shell_export_file.display()
+                );
+            }
+        }
+    }
+
+    let warnings = warnings();
+    if fail_on_warning && !warnings.is_empty() {
+        return Err(Error::Warnings(warnings).into());
+    }
+    Ok(())
+}
+
+/// Regenerates the export files of an installation from its config
+async fn export_only(args: ExportOnlyOpts) -> Result<()> {
+    initialize_logger(
+        get_log_level(&args.log_level, args.verbose, args.quiet),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
+
+    let config = Config::load(&args.name, args.config_path.as_deref())?
+        .ok_or(Error::MissingConfig(args.name))?;
+    let exports = get_config_exports(&config)?;
+    create_export_file(&config.export_file, &exports)?;
+    #[cfg(unix)]
+    for shell in [ExportShell::Fish, ExportShell::Nu] {
+        let shell_export_file = config.export_file.with_extension(shell.extension());
+        if shell_export_file.is_file() {
+            create_shell_export_file(&shell_export_file, &exports, shell)?;
+        }
+    }
+    info!("Export file '{}' regenerated", config.export_file.display());
+    Ok(())
+}
+
+/// Updates the Rust for ESP chips environment or espup itself
+async fn update(args: UpdateOpts) -> Result<()> {
+    if !args.self_update {
+        return install(args.install, InstallMode::Update).await;
+    }
+
+    initialize_logger(
+        get_log_level(
+            &args.install.log_level,
+            args.install.verbose,
+            args.install.quiet,
+        ),
+        args.install.log_module.as_deref(),
+        args.install.log_file.as_deref(),
+    );
+    self_update(args.self_version).await?;
     Ok(())
 }
 
 /// Uninstalls the Rust for ESP chips environment
 async fn uninstall(args: UninstallOpts) -> Result<()> {
-    initialize_logger(&args.log_level);
+    initialize_logger(
+        get_log_level(
+            &args.log_level,
+            args.verbose,
+            args.quiet || args.output == OutputFormat::Json,
+        ),
+        args.log_module.as_deref(),
+        args.log_file.as_deref(),
+    );
     check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
-    info!("Uninstalling the Espressif Rust ecosystem");
-    let toolchain_dir = get_rustup_home().join("toolchains").join(args.name);
-
-    if toolchain_dir.exists() {
-        Llvm::uninstall(&toolchain_dir).await?;
-
-        uninstall_gcc_toolchains(&toolchain_dir).await?;
+    let output = args.output;
+    let outcome = espup::uninstall(args).await?;
+    if output == OutputFormat::Json {
+        println!("{:#}", serde_json::json!(outcome));
+    }
+    Ok(())
+}
 
-        XtensaRust::uninstall(&toolchain_dir).await?;
+/// Checks if an option was given on the command line or in its environment variable
+fn is_set_by_user(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
 
-        remove_dir(&toolchain_dir).await?;
+/// Reads the `--from-file` install profile into the options not given on the command line
+fn apply_install_profile(args: &mut InstallOpts, matches: &ArgMatches) -> Result<()> {
+    if let Some(from_file) = &args.from_file {
+        InstallProfile::load(from_file)?.apply(args, |id| is_set_by_user(matches, id))?;
     }
+    Ok(())
+}
 
-    info!("Uninstallation successfully completed!");
+/// Reads the config of the installation into the options not given on the command line, so an
+/// update keeps the options of the installation
+fn apply_install_config(args: &mut InstallOpts, matches: &ArgMatches) -> Result<()> {
+    if let Some(config) = Config::load(&args.name, args.config_path.as_deref())? {
+        config.apply(args, |id| is_set_by_user(matches, id));
+    }
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    match Cli::parse().subcommand {
+    set_prompts_enabled(true);
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let (_, subcommand_matches) = matches.subcommand().unwrap();
+    match cli.subcommand {
+        SubCommand::AddTarget(args) => change_targets(args, true).await,
+        SubCommand::Backup(args) => backup_installation(args).await,
         SubCommand::Completions(args) => completions(args).await,
-        SubCommand::Install(args) => install(*args, InstallMode::Install).await,
-        SubCommand::Update(args) => install(*args, InstallMode::Update).await,
+        SubCommand::Doctor(args) => doctor(args).await,
+        SubCommand::Download(args) => download(*args).await,
+        SubCommand::ExportOnly(args) => export_only(args).await,
+        SubCommand::Info(args) => info(args).await,
+        SubCommand::Install(mut args) => {
+            apply_install_profile(&mut args, subcommand_matches)?;
+            install(*args, InstallMode::Install).await
+        }
+        SubCommand::List(args) => list(args).await,
+        SubCommand::Reinstall(args) => reinstall(args).await,
+        SubCommand::RemoveTarget(args) => change_targets(args, false).await,
+        SubCommand::Restore(args) => restore_installation(args).await,
+        SubCommand::ShowConfig(args) => show_config(args).await,
+        SubCommand::Update(mut args) => {
+            apply_install_config(&mut args.install, subcommand_matches)?;
+            apply_install_profile(&mut args.install, subcommand_matches)?;
+            update(*args).await
+        }
         SubCommand::Uninstall(args) => uninstall(args).await,
     }
 }
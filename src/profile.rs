@@ -0,0 +1,154 @@
+//! Install profiles, the options of an installation shared in a file.
+
+use crate::{cli::InstallOpts, cli::Linker, error::Error, targets::parse_targets};
+use log::debug;
+use serde::Deserialize;
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+/// Options of an installation read with `--from-file`, named like the command line options.
+///
+/// Keep the supported keys listed in the help of `--from-file` in sync with the fields.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct InstallProfile {
+    pub default_host: Option<String>,
+    pub esp_riscv_gcc: Option<bool>,
+    pub export_file: Option<PathBuf>,
+    pub extended_llvm: Option<bool>,
+    pub gcc_version: Option<String>,
+    pub linker: Option<Linker>,
+    pub llvm_version: Option<String>,
+    pub name: Option<String>,
+    pub nightly_version: Option<String>,
+    pub no_modify_path: Option<bool>,
+    pub std: Option<bool>,
+    pub targets: Option<ProfileTargets>,
+    pub toolchain_version: Option<String>,
+}
+
+/// Targets of a profile, as a comma or space separated string or as a list.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ProfileTargets {
+    List(Vec<String>),
+    String(String),
+}
+
+impl InstallProfile {
+    /// Reads a profile, failing on unknown options.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        debug!("Reading install profile '{}'", path.display());
+        let invalid =
+            |reason: &str| Error::InvalidInstallProfile(path.display().to_string(), reason.into());
+        let profile_str = read_to_string(path).map_err(|err| invalid(&err.to_string()))?;
+        toml::from_str(&profile_str).map_err(|err| invalid(err.message()))
+    }
+
+    /// Sets the options of the profile in `args`, except the ones that `is_set` reports as set
+    /// (e.g. from the command line).
+    pub fn apply(self, args: &mut InstallOpts, is_set: impl Fn(&str) -> bool) -> Result<(), Error> {
+        if let (Some(default_host), false) = (self.default_host, is_set("default_host")) {
+            args.default_host = Some(default_host);
+        }
+        if let (Some(esp_riscv_gcc), false) = (self.esp_riscv_gcc, is_set("esp_riscv_gcc")) {
+            args.esp_riscv_gcc = esp_riscv_gcc;
+        }
+        if let (Some(export_file), false) = (self.export_file, is_set("export_file")) {
+            args.export_file = Some(export_file);
+        }
+        if let (Some(extended_llvm), false) = (self.extended_llvm, is_set("extended_llvm")) {
+            args.extended_llvm = extended_llvm;
+        }
+        if let (Some(gcc_version), false) = (self.gcc_version, is_set("gcc_version")) {
+            args.gcc_version = Some(gcc_version);
+        }
+        if let (Some(linker), false) = (self.linker, is_set("linker")) {
+            args.linker = linker;
+        }
+        if let (Some(llvm_version), false) = (self.llvm_version, is_set("llvm_version")) {
+            args.llvm_version = Some(llvm_version);
+        }
+        if let (Some(name), false) = (self.name, is_set("name")) {
+            args.name = name;
+        }
+        if let (Some(nightly_version), false) = (self.nightly_version, is_set("nightly_version")) {
+            args.nightly_version = nightly_version;
+        }
+        if let (Some(no_modify_path), false) = (self.no_modify_path, is_set("no_modify_path")) {
+            args.no_modify_path = no_modify_path;
+        }
+        if let (Some(std), false) = (self.std, is_set("std")) {
+            args.std = std;
+        }
+        if let (Some(targets), false) = (
+            self.targets,
+            is_set("targets") || is_set("targets_from_cargo"),
+        ) {
+            args.targets = match targets {
+                ProfileTargets::List(targets) => parse_targets(&targets.join(","))?,
+                ProfileTargets::String(targets) => parse_targets(&targets)?,
+            };
+        }
+        if let (Some(toolchain_version), false) =
+            (self.toolchain_version, is_set("toolchain_version"))
+        {
+            args.toolchain_version = Some(toolchain_version);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cli::InstallOpts, profile::InstallProfile, targets::Target};
+    use clap::Parser;
+    use std::{
+        collections::HashSet,
+        fs::{read_to_string, write},
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let profile = temp_dir.path().join("espup.install.toml");
+
+        write(
+            &profile,
+            "targets = [\"esp32\", \"esp32c3\"]\ntoolchain-version = \"1.82.0.3\"\nname = \"team\"\n",
+        )
+        .unwrap();
+        let mut args = InstallOpts::parse_from(["install", "--name", "mine"]);
+        InstallProfile::load(&profile)
+            .unwrap()
+            .apply(&mut args, |id| id == "name")
+            .unwrap();
+        let targets: HashSet<Target> = [Target::ESP32, Target::ESP32C3].into_iter().collect();
+        assert_eq!(args.targets, targets);
+        assert_eq!(args.toolchain_version.as_deref(), Some("1.82.0.3"));
+        assert_eq!(args.name, "mine");
+
+        // Unreadable file, with the reason of the failure
+        let missing = temp_dir.path().join("missing.toml");
+        let io_error = read_to_string(&missing).unwrap_err().to_string();
+        assert!(InstallProfile::load(&missing)
+            .unwrap_err()
+            .to_string()
+            .ends_with(&io_error));
+
+        // Unknown options
+        write(&profile, "targets = \"esp32\"\ntoolchain = \"1.82.0.3\"\n").unwrap();
+        assert!(InstallProfile::load(&profile).is_err());
+
+        // Invalid targets
+        write(&profile, "targets = \"esp42\"\n").unwrap();
+        let mut args = InstallOpts::parse_from(["install"]);
+        assert!(InstallProfile::load(&profile)
+            .unwrap()
+            .apply(&mut args, |_| false)
+            .is_err());
+    }
+}
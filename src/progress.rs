@@ -0,0 +1,110 @@
+//! Progress reporting of installations.
+
+use crate::toolchain::{DOWNLOAD_CNT, PROCESS_BARS, PROGRESS_LOGS};
+use log::{debug, info};
+use std::{
+    collections::HashMap,
+    sync::{atomic, Arc, Mutex, RwLock},
+};
+
+lazy_static::lazy_static! {
+    static ref PROGRESS_REPORTER: RwLock<Arc<dyn ProgressReporter>> =
+        RwLock::new(Arc::new(ConsoleReporter::default()));
+}
+
+/// Receives the progress events of an installation.
+pub trait ProgressReporter: Send + Sync {
+    /// Called when the download of a file starts, with its size if it's known.
+    fn on_download_start(&self, file_name: &str, len: Option<u64>);
+
+    /// Called as a file is downloaded, with the bytes downloaded so far.
+    fn on_download_progress(&self, file_name: &str, downloaded: u64);
+
+    /// Called when the download of a file completes.
+    fn on_download_finish(&self, _file_name: &str) {}
+
+    /// Called when a step of the installation starts, e.g. the installation of a component.
+    fn on_step(&self, step: &str);
+}
+
+/// Sets the reporter of the progress events, instead of the console one.
+pub fn set_progress_reporter(reporter: Arc<dyn ProgressReporter>) {
+    *PROGRESS_REPORTER.write().unwrap() = reporter;
+}
+
+/// Gets the reporter of the progress events.
+pub fn progress_reporter() -> Arc<dyn ProgressReporter> {
+    PROGRESS_REPORTER.read().unwrap().clone()
+}
+
+/// Download in progress of the console reporter.
+struct Download {
+    bar: indicatif::ProgressBar,
+    len: Option<u64>,
+    logged_percent: u64,
+}
+
+/// Reporter that draws progress bars, or logs the progress when they aren't drawn.
+#[derive(Default)]
+pub struct ConsoleReporter {
+    downloads: Mutex<HashMap<String, Download>>,
+}
+
+impl ProgressReporter for ConsoleReporter {
+    fn on_download_start(&self, file_name: &str, len: Option<u64>) {
+        // draw a progress bar
+        let sty = indicatif::ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
+        )
+        .unwrap()
+        .progress_chars("##-");
+        let bar = len
+            .map(indicatif::ProgressBar::new)
+            .unwrap_or(indicatif::ProgressBar::no_length());
+        let bar = PROCESS_BARS.add(bar);
+        bar.set_style(sty);
+        bar.set_message(file_name.to_string());
+        DOWNLOAD_CNT.fetch_add(1, atomic::Ordering::Relaxed);
+        self.downloads.lock().unwrap().insert(
+            file_name.to_string(),
+            Download {
+                bar,
+                len,
+                logged_percent: 0,
+            },
+        );
+    }
+
+    fn on_download_progress(&self, file_name: &str, downloaded: u64) {
+        let mut downloads = self.downloads.lock().unwrap();
+        let Some(download) = downloads.get_mut(file_name) else {
+            return;
+        };
+        download.bar.set_position(downloaded);
+        if let (true, Some(len)) = (PROGRESS_LOGS.load(atomic::Ordering::Relaxed), download.len) {
+            let percent = downloaded * 100 / len.max(1);
+            if percent >= download.logged_percent + 10 {
+                download.logged_percent = percent - percent % 10;
+                info!("Downloading '{}': {}%", file_name, download.logged_percent);
+            }
+        }
+    }
+
+    fn on_download_finish(&self, file_name: &str) {
+        if let Some(download) = self.downloads.lock().unwrap().remove(file_name) {
+            download
+                .bar
+                .finish_with_message(format!("{} download complete", file_name));
+        }
+        // leave the progress bar after completion
+        if DOWNLOAD_CNT.fetch_sub(1, atomic::Ordering::Relaxed) == 1 {
+            // clear all progress bars
+            PROCESS_BARS.clear().unwrap();
+            info!("All downloads complete");
+        }
+    }
+
+    fn on_step(&self, step: &str) {
+        debug!("{}", step);
+    }
+}
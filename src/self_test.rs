@@ -0,0 +1,97 @@
+//! Compilation check of an installation.
+
+use crate::{env::create_export_file, error::Error, targets::Target};
+use log::{debug, info};
+use std::{
+    fs::{create_dir_all, write},
+    process::{Command, Stdio},
+};
+use tempfile::TempDir;
+
+const CARGO_TOML: &str = r#"[package]
+name = "espup-self-test"
+version = "0.0.0"
+edition = "2021"
+
+[dependencies]
+"#;
+const MAIN_RS: &str = r#"#![no_std]
+#![no_main]
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    loop {}
+}
+"#;
+
+/// Builds and links a `no_std` binary for a target with an installed toolchain, after sourcing
+/// an export file with the exports of the installation.
+///
+/// Linking checks the linker of the target too: the GCC toolchain for the Xtensa targets.
+pub fn self_test(target: Target, toolchain: &str, exports: &[String]) -> Result<(), Error> {
+    info!(
+        "Building a test crate for '{}' with '{}'",
+        target, toolchain
+    );
+    let project = TempDir::new()?;
+    create_dir_all(project.path().join("src"))?;
+    write(project.path().join("Cargo.toml"), CARGO_TOML)?;
+    write(project.path().join("src").join("main.rs"), MAIN_RS)?;
+    #[cfg(unix)]
+    let export_file = project.path().join("export-esp.sh");
+    #[cfg(windows)]
+    let export_file = project.path().join("export-esp.ps1");
+    create_export_file(&export_file, exports)?;
+
+    let mut cargo = format!(
+        "cargo +{toolchain} build --release --target {}",
+        target.no_std_triple()
+    );
+    if target.is_xtensa() {
+        cargo.push_str(" -Zbuild-std=core");
+    }
+    #[cfg(unix)]
+    let mut command = Command::new("sh");
+    #[cfg(unix)]
+    command
+        .arg("-c")
+        .arg(format!(". \"{}\" && {cargo}", export_file.display()));
+    #[cfg(windows)]
+    let mut command = Command::new("powershell");
+    #[cfg(windows)]
+    command.args([
+        "-NoProfile",
+        "-Command",
+        &format!(". '{}'; {cargo}; exit $LASTEXITCODE", export_file.display()),
+    ]);
+    if target.is_xtensa() {
+        // Without a runtime, there is no C startup code to link
+        command.env("RUSTFLAGS", "-C link-arg=-nostartfiles");
+    }
+    debug!("Running '{:?}'", command);
+    let output = command
+        .current_dir(project.path())
+        .stdout(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!("Test crate build output:\n{}", stderr);
+        return Err(Error::SelfTest(
+            target.to_string(),
+            stderr
+                .lines()
+                .rev()
+                .find(|line| line.trim_start().starts_with("error"))
+                .unwrap_or("the build failed")
+                .trim()
+                .to_string(),
+        ));
+    }
+    info!("Test crate for '{}' built successfully", target);
+    Ok(())
+}
@@ -3,11 +3,33 @@
 use crate::error::Error;
 use log::debug;
 use miette::Result;
-use std::{collections::HashSet, str::FromStr};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashSet},
+    env,
+    fs::read_to_string,
+    path::Path,
+    str::FromStr,
+};
 use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-#[derive(Clone, Copy, EnumIter, EnumString, PartialEq, Hash, Eq, Debug, Display)]
+#[derive(
+    Clone,
+    Copy,
+    EnumIter,
+    EnumString,
+    PartialEq,
+    Hash,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Display,
+    Serialize,
+    Deserialize,
+)]
 #[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum Target {
     /// Xtensa LX6 based dual core
     ESP32 = 0,
@@ -37,6 +59,50 @@ impl Target {
     pub fn is_xtensa(&self) -> bool {
         matches!(self, Target::ESP32 | Target::ESP32S2 | Target::ESP32S3)
     }
+
+    /// Returns the Rust target triple for `no_std` applications of the chip.
+    pub fn no_std_triple(&self) -> &'static str {
+        match self {
+            Target::ESP32 => "xtensa-esp32-none-elf",
+            Target::ESP32S2 => "xtensa-esp32s2-none-elf",
+            Target::ESP32S3 => "xtensa-esp32s3-none-elf",
+            Target::ESP32C2 | Target::ESP32C3 => "riscv32imc-unknown-none-elf",
+            Target::ESP32C6 | Target::ESP32H2 => "riscv32imac-unknown-none-elf",
+            Target::ESP32P4 => "riscv32imafc-unknown-none-elf",
+        }
+    }
+}
+
+/// Targets of an installation after adding or removing some of them.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TargetsChange {
+    /// Targets of the installation after the change, empty if all of them were removed.
+    pub targets: HashSet<Target>,
+    /// Whether Xtensa Rust and LLVM are no longer needed, as the last Xtensa target was removed.
+    pub drops_xtensa: bool,
+}
+
+/// Returns the targets of an installation after adding `changed` to the `installed` ones, or
+/// removing them from it when `add` isn't set.
+pub fn get_targets_change(
+    installed: &BTreeSet<Target>,
+    changed: &HashSet<Target>,
+    add: bool,
+) -> TargetsChange {
+    let targets: HashSet<Target> = if add {
+        installed.iter().chain(changed).copied().collect()
+    } else {
+        installed
+            .iter()
+            .filter(|target| !changed.contains(target))
+            .copied()
+            .collect()
+    };
+    TargetsChange {
+        drops_xtensa: installed.iter().any(Target::is_xtensa)
+            && !targets.iter().any(Target::is_xtensa),
+        targets,
+    }
 }
 
 /// Returns a vector of Chips from a comma or space separated string.
@@ -46,14 +112,28 @@ pub fn parse_targets(targets_str: &str) -> Result<HashSet<Target>, Error> {
     let targets_str = targets_str.to_lowercase();
     let targets_str = targets_str.trim();
 
-    let targets: HashSet<Target> = if targets_str.contains("all") {
+    let targets: HashSet<Target> = if targets_str == "auto" {
+        let manifest = env::current_dir()
+            .map_err(|_| {
+                Error::InvalidCargoMetadata("Cargo.toml".into(), "unknown current directory".into())
+            })?
+            .join("Cargo.toml");
+        targets_from_cargo_metadata(&manifest)?
+    } else if targets_str.split([',', ' ']).any(|target| target == "all") {
         Target::iter().collect()
     } else {
         let mut targets = HashSet::new();
         for target in targets_str.split([',', ' ']) {
-            targets.insert(
-                Target::from_str(target).map_err(|_| Error::UnsupportedTarget(target.into()))?,
-            );
+            match target {
+                "xtensa" | "all-xtensa" => targets.extend(Target::iter().filter(Target::is_xtensa)),
+                "riscv" | "all-riscv" => targets.extend(Target::iter().filter(Target::is_riscv)),
+                _ => {
+                    targets.insert(
+                        Target::from_str(target)
+                            .map_err(|_| Error::UnsupportedTarget(target.into()))?,
+                    );
+                }
+            }
         }
 
         targets
@@ -63,10 +143,98 @@ pub fn parse_targets(targets_str: &str) -> Result<HashSet<Target>, Error> {
     Ok(targets)
 }
 
+/// Returns the targets listed in the `package.metadata.espup.targets` table of a `Cargo.toml`.
+pub fn targets_from_cargo_metadata(manifest: &Path) -> Result<HashSet<Target>, Error> {
+    debug!("Reading targets from '{}'", manifest.display());
+    let invalid =
+        |reason: &str| Error::InvalidCargoMetadata(manifest.display().to_string(), reason.into());
+
+    let manifest_str = read_to_string(manifest).map_err(|_| invalid("file not found"))?;
+    let manifest_toml: toml::Table = manifest_str.parse().map_err(|_| invalid("invalid TOML"))?;
+    let targets = manifest_toml
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("espup"))
+        .and_then(|espup| espup.get("targets"))
+        .ok_or_else(|| invalid("missing `package.metadata.espup.targets`"))?;
+
+    let targets_str = match targets {
+        toml::Value::String(targets) => targets.clone(),
+        toml::Value::Array(targets) => targets
+            .iter()
+            .map(|target| target.as_str().map(str::to_string))
+            .collect::<Option<Vec<String>>>()
+            .ok_or_else(|| invalid("`targets` must only contain strings"))?
+            .join(","),
+        _ => return Err(invalid("`targets` must be a string or a list of strings")),
+    };
+    if targets_str.trim().is_empty() || targets_str.to_lowercase().contains("auto") {
+        return Err(invalid(
+            "`targets` must list at least one target and can't be `auto`",
+        ));
+    }
+
+    parse_targets(&targets_str)
+}
+
+/// Returns the chips of the `build.target` triples of a Cargo `config.toml`.
+///
+/// RISC-V triples are shared by several chips, so all of them are returned.
+pub fn targets_from_cargo_config(config: &Path) -> Result<HashSet<Target>, Error> {
+    debug!("Reading targets from '{}'", config.display());
+    let invalid =
+        |reason: &str| Error::InvalidCargoMetadata(config.display().to_string(), reason.into());
+
+    let config_str = read_to_string(config).map_err(|_| invalid("file not found"))?;
+    let config_toml: toml::Table = config_str.parse().map_err(|_| invalid("invalid TOML"))?;
+    let triples = match config_toml
+        .get("build")
+        .and_then(|build| build.get("target"))
+        .ok_or_else(|| invalid("missing `build.target`"))?
+    {
+        toml::Value::String(triple) => vec![triple.as_str()],
+        toml::Value::Array(triples) => triples.iter().filter_map(toml::Value::as_str).collect(),
+        _ => return Err(invalid("`target` must be a string or a list of strings")),
+    };
+
+    let mut targets = HashSet::new();
+    for triple in triples {
+        let chips: &[Target] = match triple.split('-').next().unwrap_or_default() {
+            "xtensa" => match triple.split('-').nth(1) {
+                Some("esp32") => &[Target::ESP32],
+                Some("esp32s2") => &[Target::ESP32S2],
+                Some("esp32s3") => &[Target::ESP32S3],
+                _ => &[],
+            },
+            "riscv32imc" => &[Target::ESP32C2, Target::ESP32C3],
+            "riscv32imac" => &[Target::ESP32C6, Target::ESP32H2],
+            "riscv32imafc" => &[Target::ESP32P4],
+            _ => &[],
+        };
+        if chips.is_empty() {
+            debug!("Ignoring target '{}'", triple);
+        }
+        targets.extend(chips);
+    }
+    if targets.is_empty() {
+        return Err(invalid("`build.target` has no ESP target"));
+    }
+
+    debug!("Parsed targets: {:?}", targets);
+    Ok(targets)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::targets::{parse_targets, Target};
-    use std::collections::HashSet;
+    use crate::targets::{
+        get_targets_change, parse_targets, targets_from_cargo_config, targets_from_cargo_metadata,
+        Target, TargetsChange,
+    };
+    use std::{
+        collections::{BTreeSet, HashSet},
+        fs::write,
+    };
+    use tempfile::TempDir;
 
     #[test]
     #[allow(unused_variables)]
@@ -97,5 +265,134 @@ mod tests {
         .into_iter()
         .collect();
         assert!(matches!(parse_targets("all"), Ok(targets)));
+        // Architectures
+        let targets: HashSet<Target> = [Target::ESP32, Target::ESP32S2, Target::ESP32S3]
+            .into_iter()
+            .collect();
+        assert_eq!(parse_targets("xtensa").unwrap(), targets);
+        let targets: HashSet<Target> = [
+            Target::ESP32C2,
+            Target::ESP32C3,
+            Target::ESP32C6,
+            Target::ESP32H2,
+            Target::ESP32P4,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(parse_targets("riscv").unwrap(), targets);
+        assert_eq!(parse_targets("riscv,esp32c3").unwrap(), targets);
+        assert_eq!(parse_targets("all-riscv").unwrap(), targets);
+        let targets: HashSet<Target> = [Target::ESP32, Target::ESP32S2, Target::ESP32S3]
+            .into_iter()
+            .collect();
+        assert_eq!(parse_targets("all-xtensa").unwrap(), targets);
+    }
+
+    #[test]
+    fn test_targets_from_cargo_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest = temp_dir.path().join("Cargo.toml");
+
+        // List of targets
+        write(
+            &manifest,
+            "[package]\nname = \"app\"\n\n[package.metadata.espup]\ntargets = [\"esp32\", \"esp32c3\"]\n",
+        )
+        .unwrap();
+        let targets: HashSet<Target> = [Target::ESP32, Target::ESP32C3].into_iter().collect();
+        assert_eq!(targets_from_cargo_metadata(&manifest).unwrap(), targets);
+
+        // Comma separated string
+        write(
+            &manifest,
+            "[package]\nname = \"app\"\n\n[package.metadata.espup]\ntargets = \"esp32s3\"\n",
+        )
+        .unwrap();
+        let targets: HashSet<Target> = [Target::ESP32S3].into_iter().collect();
+        assert_eq!(targets_from_cargo_metadata(&manifest).unwrap(), targets);
+
+        // Missing metadata
+        write(&manifest, "[package]\nname = \"app\"\n").unwrap();
+        assert!(targets_from_cargo_metadata(&manifest).is_err());
+
+        // Malformed metadata
+        write(
+            &manifest,
+            "[package]\nname = \"app\"\n\n[package.metadata.espup]\ntargets = [1, 2]\n",
+        )
+        .unwrap();
+        assert!(targets_from_cargo_metadata(&manifest).is_err());
+        write(
+            &manifest,
+            "[package.metadata.espup]\ntargets = [\"esp32\", \"esp42\"]\n",
+        )
+        .unwrap();
+        assert!(targets_from_cargo_metadata(&manifest).is_err());
+
+        // Missing manifest
+        assert!(targets_from_cargo_metadata(&temp_dir.path().join("missing.toml")).is_err());
+    }
+
+    #[test]
+    fn test_targets_from_cargo_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = temp_dir.path().join("config.toml");
+
+        write(&config, "[build]\ntarget = \"xtensa-esp32s3-none-elf\"\n").unwrap();
+        let targets: HashSet<Target> = [Target::ESP32S3].into_iter().collect();
+        assert_eq!(targets_from_cargo_config(&config).unwrap(), targets);
+
+        write(
+            &config,
+            "[build]\ntarget = [\"riscv32imc-unknown-none-elf\", \"xtensa-esp32-espidf\"]\n",
+        )
+        .unwrap();
+        let targets: HashSet<Target> = [Target::ESP32, Target::ESP32C2, Target::ESP32C3]
+            .into_iter()
+            .collect();
+        assert_eq!(targets_from_cargo_config(&config).unwrap(), targets);
+
+        // No ESP target
+        write(&config, "[build]\ntarget = \"x86_64-unknown-linux-gnu\"\n").unwrap();
+        assert!(targets_from_cargo_config(&config).is_err());
+        write(&config, "[alias]\nb = \"build\"\n").unwrap();
+        assert!(targets_from_cargo_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_get_targets_change() {
+        let installed: BTreeSet<Target> = [Target::ESP32, Target::ESP32C3].into_iter().collect();
+
+        // Adding a target
+        let change = get_targets_change(&installed, &[Target::ESP32S3].into_iter().collect(), true);
+        assert_eq!(
+            change,
+            TargetsChange {
+                targets: [Target::ESP32, Target::ESP32C3, Target::ESP32S3]
+                    .into_iter()
+                    .collect(),
+                drops_xtensa: false,
+            }
+        );
+
+        // Removing a RISC-V target
+        let change =
+            get_targets_change(&installed, &[Target::ESP32C3].into_iter().collect(), false);
+        assert_eq!(change.targets, [Target::ESP32].into_iter().collect());
+        assert!(!change.drops_xtensa);
+
+        // Removing the last Xtensa target
+        let change = get_targets_change(&installed, &[Target::ESP32].into_iter().collect(), false);
+        assert_eq!(change.targets, [Target::ESP32C3].into_iter().collect());
+        assert!(change.drops_xtensa);
+
+        // Removing everything
+        let change = get_targets_change(
+            &installed,
+            &[Target::ESP32, Target::ESP32C3].into_iter().collect(),
+            false,
+        );
+        assert!(change.targets.is_empty());
+        assert!(change.drops_xtensa);
     }
 }
@@ -5,7 +5,7 @@ use crate::env::{get_windows_path_var, set_env_variable};
 use crate::{
     error::Error,
     host_triple::HostTriple,
-    toolchain::{download_file, Installable},
+    toolchain::{download_file, github_query, Installable},
 };
 use async_trait::async_trait;
 use log::{debug, info, warn};
@@ -16,9 +16,15 @@ use std::{env, fs::File};
 use tokio::fs::remove_dir_all;
 
 const DEFAULT_GCC_REPOSITORY: &str = "https://github.com/espressif/crosstool-NG/releases/download";
-const DEFAULT_GCC_RELEASE: &str = "14.2.0_20240906";
+const GCC_RELEASES_API_URL: &str =
+    "https://api.github.com/repos/espressif/crosstool-NG/releases/tags";
+pub const DEFAULT_GCC_RELEASE: &str = "14.2.0_20240906";
 pub const RISCV_GCC: &str = "riscv32-esp-elf";
 pub const XTENSA_GCC: &str = "xtensa-esp-elf";
+/// Approximate disk space of the RISC-V GCC toolchain.
+const RISCV_GCC_INSTALLED_SIZE: u64 = 1400 * 1024 * 1024;
+/// Approximate disk space of the Xtensa GCC toolchain.
+const XTENSA_GCC_INSTALLED_SIZE: u64 = 500 * 1024 * 1024;
 
 #[derive(Debug, Clone)]
 pub struct Gcc {
@@ -28,6 +34,10 @@ pub struct Gcc {
     pub arch: String,
     /// GCC Toolchain path.
     pub path: PathBuf,
+    /// Whether to remove a previous installation instead of reusing it.
+    pub force: bool,
+    /// GCC Toolchain version.
+    pub version: String,
 }
 
 impl Gcc {
@@ -40,12 +50,39 @@ impl Gcc {
         }
     }
 
+    /// Returns whether the toolchain is already installed.
+    fn is_installed(&self) -> bool {
+        #[cfg(unix)]
+        let is_installed = self.path.exists();
+        #[cfg(windows)]
+        let is_installed = self.path.join(&self.arch).join(&self.version).exists();
+        is_installed
+    }
+
+    /// Gets the URL of the GCC artifact for the host.
+    pub fn get_dist_url(&self) -> String {
+        let gcc_file = format!(
+            "{}-{}-{}.{}",
+            self.arch,
+            self.version,
+            get_arch(&self.host_triple).unwrap(),
+            get_artifact_extension(&self.host_triple)
+        );
+        format!("{DEFAULT_GCC_REPOSITORY}/esp-{}/{gcc_file}", self.version)
+    }
+
     /// Create a new instance with default values and proper toolchain name.
-    pub fn new(arch: &str, host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+    ///
+    /// `version` selects a GCC release instead of the default one.
+    pub fn new(
+        arch: &str,
+        host_triple: &HostTriple,
+        toolchain_path: &Path,
+        version: Option<&str>,
+    ) -> Self {
+        let version = version.unwrap_or(DEFAULT_GCC_RELEASE).to_string();
         #[cfg(unix)]
-        let path = toolchain_path
-            .join(arch)
-            .join(format!("esp-{DEFAULT_GCC_RELEASE}"));
+        let path = toolchain_path.join(arch).join(format!("esp-{version}"));
         #[cfg(windows)]
         let path: PathBuf = toolchain_path.into();
 
@@ -53,6 +90,8 @@ impl Gcc {
             host_triple: host_triple.clone(),
             arch: arch.to_string(),
             path,
+            force: false,
+            version,
         }
     }
 }
@@ -64,66 +103,77 @@ impl Installable for Gcc {
         info!("Installing GCC ({})", self.arch);
         debug!("GCC path: {}", self.path.display());
 
-        #[cfg(unix)]
-        let is_installed = self.path.exists();
-        #[cfg(windows)]
-        let is_installed = self
-            .path
-            .join(&self.arch)
-            .join(DEFAULT_GCC_RELEASE)
-            .exists();
-
-        if is_installed {
+        let is_installed = self.is_installed();
+        if is_installed && self.force {
+            #[cfg(unix)]
+            let install_path = self.path.clone();
+            #[cfg(windows)]
+            let install_path = self.path.join(&self.arch);
+            info!(
+                "Removing previous installation of GCC in: '{}'",
+                install_path.display()
+            );
+            remove_dir_all(&install_path).await?;
+        }
+        if is_installed && !self.force {
             warn!(
                 "Previous installation of GCC exists in: '{}'. Reusing this installation",
                 &self.path.display()
             );
         } else {
-            let gcc_file = format!(
-                "{}-{}-{}.{}",
-                self.arch,
-                DEFAULT_GCC_RELEASE,
-                get_arch(&self.host_triple).unwrap(),
-                extension
-            );
-            let gcc_dist_url = format!(
-                "{}/esp-{}/{}",
-                DEFAULT_GCC_REPOSITORY, DEFAULT_GCC_RELEASE, gcc_file
-            );
             download_file(
-                gcc_dist_url,
+                self.get_dist_url(),
                 &format!("{}.{}", &self.arch, extension),
                 &self.path.display().to_string(),
                 true,
                 false,
+                false,
             )
             .await?;
         }
-        let mut exports: Vec<String> = Vec::new();
-
         #[cfg(windows)]
         if cfg!(windows) {
-            File::create(self.path.join(&self.arch).join(DEFAULT_GCC_RELEASE))?;
+            File::create(self.path.join(&self.arch).join(&self.version))?;
 
-            exports.push(format!(
-                "$Env:PATH = \"{};\" + $Env:PATH",
-                &self.get_bin_path()
-            ));
             if self.arch == RISCV_GCC {
                 env::set_var("RISCV_GCC", self.get_bin_path());
             } else {
                 env::set_var("XTENSA_GCC", self.get_bin_path());
             }
         }
-        #[cfg(unix)]
-        exports.push(format!("export PATH=\"{}:$PATH\"", &self.get_bin_path()));
 
-        Ok(exports)
+        Ok(self.exports())
+    }
+
+    fn exports(&self) -> Vec<String> {
+        #[cfg(windows)]
+        let export = format!("$Env:PATH = \"{};\" + $Env:PATH", &self.get_bin_path());
+        #[cfg(unix)]
+        let export = format!("export PATH=\"{}:$PATH\"", &self.get_bin_path());
+        vec![export]
     }
 
     fn name(&self) -> String {
         format!("GCC ({})", self.arch)
     }
+
+    fn license(&self) -> Option<&'static str> {
+        Some("GPL-3.0-or-later WITH GCC-exception-3.1")
+    }
+
+    fn artifacts(&self) -> Vec<String> {
+        vec![self.get_dist_url()]
+    }
+
+    fn installed_size(&self) -> u64 {
+        if self.is_installed() && !self.force {
+            0
+        } else if self.arch == RISCV_GCC {
+            RISCV_GCC_INSTALLED_SIZE
+        } else {
+            XTENSA_GCC_INSTALLED_SIZE
+        }
+    }
 }
 
 /// Gets the name of the GCC arch based on the host triple.
@@ -147,6 +197,19 @@ fn get_artifact_extension(host_triple: &HostTriple) -> &str {
     }
 }
 
+/// Checks that a GCC version is a release of Espressif's crosstool-NG.
+pub async fn check_gcc_version(version: &str) -> Result<(), Error> {
+    let tag = format!("esp-{version}");
+    let url = format!("{GCC_RELEASES_API_URL}/{tag}");
+    let json = tokio::task::spawn_blocking(move || github_query(&url))
+        .await
+        .unwrap()?;
+    if json["tag_name"].as_str() != Some(tag.as_str()) {
+        return Err(Error::UnsupportedGccVersion(version.to_string()));
+    }
+    Ok(())
+}
+
 /// Checks if the toolchain is pressent, if present uninstalls it.
 pub async fn uninstall_gcc_toolchains(toolchain_path: &Path) -> Result<(), Error> {
     info!("Uninstalling GCC");
@@ -154,27 +217,34 @@ pub async fn uninstall_gcc_toolchains(toolchain_path: &Path) -> Result<(), Error
     let gcc_toolchains = vec![XTENSA_GCC, RISCV_GCC];
 
     for toolchain in gcc_toolchains {
-        let gcc_path = toolchain_path.join(toolchain);
-        if gcc_path.exists() {
-            #[cfg(windows)]
-            if cfg!(windows) {
-                let mut updated_path = get_windows_path_var()?;
-                let gcc_version_path = format!(
-                    "{}\\esp-{}\\{}\\bin",
-                    gcc_path.display(),
-                    DEFAULT_GCC_RELEASE,
-                    toolchain
-                );
-                updated_path = updated_path.replace(&format!("{gcc_version_path};"), "");
-                let bin_path = format!("{}\\bin", gcc_path.display());
-                updated_path = updated_path.replace(&format!("{bin_path};"), "");
-
-                set_env_variable("PATH", &updated_path)?;
-            }
-            remove_dir_all(&gcc_path)
-                .await
-                .map_err(|_| Error::RemoveDirectory(gcc_path.display().to_string()))?;
+        uninstall_gcc_toolchain(toolchain_path, toolchain).await?;
+    }
+
+    Ok(())
+}
+
+/// Checks if the GCC toolchain of the given architecture is present, if present uninstalls it.
+pub async fn uninstall_gcc_toolchain(toolchain_path: &Path, arch: &str) -> Result<(), Error> {
+    let gcc_path = toolchain_path.join(arch);
+    if gcc_path.exists() {
+        #[cfg(windows)]
+        if cfg!(windows) {
+            let mut updated_path = get_windows_path_var()?;
+            let gcc_version_path = format!(
+                "{}\\esp-{}\\{}\\bin",
+                gcc_path.display(),
+                DEFAULT_GCC_RELEASE,
+                arch
+            );
+            updated_path = updated_path.replace(&format!("{gcc_version_path};"), "");
+            let bin_path = format!("{}\\bin", gcc_path.display());
+            updated_path = updated_path.replace(&format!("{bin_path};"), "");
+
+            set_env_variable("PATH", &updated_path)?;
         }
+        remove_dir_all(&gcc_path)
+            .await
+            .map_err(|_| Error::RemoveDirectory(gcc_path.display().to_string()))?;
     }
 
     Ok(())
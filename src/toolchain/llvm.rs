@@ -2,14 +2,16 @@
 
 #[cfg(windows)]
 use crate::env::{delete_env_variable, get_windows_path_var, set_env_variable};
+#[cfg(unix)]
+use crate::toolchain::rust::get_espup_dir;
 use crate::{
     error::Error,
     host_triple::HostTriple,
-    toolchain::{download_file, rust::RE_EXTENDED_SEMANTIC_VERSION, Installable},
+    toolchain::{
+        download_file, rust::RE_EXTENDED_SEMANTIC_VERSION, select_release_asset, Installable,
+    },
 };
 use async_trait::async_trait;
-#[cfg(unix)]
-use directories::BaseDirs;
 use log::{info, warn};
 use miette::Result;
 use regex::Regex;
@@ -28,6 +30,18 @@ const DEFAULT_LLVM_16_VERSION: &str = "esp-16.0.4-20231113";
 const DEFAULT_LLVM_17_VERSION: &str = "esp-17.0.1_20240419";
 const DEFAULT_LLVM_18_VERSION: &str = "esp-18.1.2_20240912";
 const DEFAULT_LLVM_19_VERSION: &str = "esp-19.1.2_20250211";
+/// Espressif LLVM releases that can be selected with `--llvm-version`, by major version.
+pub const LLVM_VERSIONS: [(&str, &str); 5] = [
+    ("15", DEFAULT_LLVM_15_VERSION),
+    ("16", DEFAULT_LLVM_16_VERSION),
+    ("17", DEFAULT_LLVM_17_VERSION),
+    ("18", DEFAULT_LLVM_18_VERSION),
+    ("19", DEFAULT_LLVM_19_VERSION),
+];
+/// Approximate disk space of the LLVM libraries.
+const LLVM_INSTALLED_SIZE: u64 = 300 * 1024 * 1024;
+/// Approximate disk space of the whole LLVM.
+const LLVM_EXTENDED_INSTALLED_SIZE: u64 = 700 * 1024 * 1024;
 pub const CLANG_NAME: &str = "xtensa-esp32-elf-clang";
 
 #[derive(Debug, Clone, Default)]
@@ -38,8 +52,12 @@ pub struct Llvm {
     pub file_name_libs: Option<String>,
     /// LLVM "full" toolchain file name.
     pub file_name_full: Option<String>,
+    /// Whether to remove a previous installation instead of reusing it.
+    pub force: bool,
     /// Host triple.
     pub host_triple: HostTriple,
+    /// Whether to skip the verification of the downloaded artifacts.
+    pub no_verify: bool,
     /// LLVM Toolchain path.
     pub path: PathBuf,
     /// The repository containing LLVM sources.
@@ -78,7 +96,7 @@ impl Llvm {
     }
 
     /// Gets the binary path.
-    fn get_lib_path(&self) -> String {
+    pub fn get_lib_path(&self) -> String {
         match std::cfg!(windows) {
             true => format!("{}/esp-clang/bin", self.path.to_str().unwrap()).replace('/', "\\"),
             false => format!("{}/esp-clang/lib", self.path.to_str().unwrap()),
@@ -86,7 +104,7 @@ impl Llvm {
     }
 
     /// Gets the binary path of clang
-    fn get_bin_path(&self) -> String {
+    pub fn get_bin_path(&self) -> String {
         match std::cfg!(windows) {
             true => format!("{}/esp-clang/bin/clang.exe", self.path.to_str().unwrap())
                 .replace('/', "\\"),
@@ -95,11 +113,15 @@ impl Llvm {
     }
 
     /// Create a new instance with default values and proper toolchain version.
+    ///
+    /// `llvm_version` selects a major LLVM version instead of the one matching the Xtensa Rust
+    /// version.
     pub fn new(
         toolchain_path: &Path,
         host_triple: &HostTriple,
         extended: bool,
         xtensa_rust_version: &str,
+        llvm_version: Option<&str>,
     ) -> Result<Self, Error> {
         let re_extended: Regex = Regex::new(RE_EXTENDED_SEMANTIC_VERSION).unwrap();
         let (major, minor, patch, subpatch) = match re_extended.captures(xtensa_rust_version) {
@@ -113,7 +135,22 @@ impl Llvm {
         };
 
         // Use LLVM 15 for versions 1.69.0.0 and below and LLVM 16 for versions 1.77.0 and bellow
-        let version = if (major == 1 && minor == 69 && patch == 0 && subpatch == 0)
+        let version = if let Some(llvm_version) = llvm_version {
+            LLVM_VERSIONS
+                .iter()
+                .find(|(major, _)| *major == llvm_version)
+                .map(|(_, version)| version.to_string())
+                .ok_or_else(|| {
+                    Error::UnsupportedLlvmVersion(
+                        llvm_version.to_string(),
+                        LLVM_VERSIONS
+                            .iter()
+                            .map(|(major, _)| *major)
+                            .collect::<Vec<&str>>()
+                            .join(", "),
+                    )
+                })?
+        } else if (major == 1 && minor == 69 && patch == 0 && subpatch == 0)
             || (major == 1 && minor < 69)
         {
             DEFAULT_LLVM_15_VERSION.to_string()
@@ -188,20 +225,50 @@ impl Llvm {
             extended,
             file_name_libs,
             file_name_full,
+            force: false,
             host_triple: host_triple.clone(),
+            no_verify: false,
             path,
             repository_url,
             version,
         })
     }
 
-    /// Uninstall LLVM toolchain.
-    pub async fn uninstall(toolchain_path: &Path) -> Result<(), Error> {
+    /// Uses the host artifacts of a GitHub release instead of the default ones.
+    pub fn use_release_assets(
+        &mut self,
+        assets: &[String],
+        release_url: &str,
+    ) -> Result<(), Error> {
+        let arch = Self::get_arch(&self.host_triple, &self.version);
+        if self.file_name_libs.is_some() {
+            let asset = select_release_asset(assets, "LLVM libs", release_url, |name| {
+                name.starts_with("libs") && name.contains(&arch)
+            })?;
+            let (repository_url, file_name) = asset.rsplit_once('/').unwrap();
+            self.repository_url = repository_url.to_string();
+            self.file_name_libs = Some(file_name.to_string());
+        }
+        if self.file_name_full.is_some() {
+            let asset = select_release_asset(assets, "LLVM", release_url, |name| {
+                !name.starts_with("libs") && name.contains(&arch)
+            })?;
+            let (repository_url, file_name) = asset.rsplit_once('/').unwrap();
+            self.repository_url = repository_url.to_string();
+            self.file_name_full = Some(file_name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Uninstall LLVM toolchain, restoring the user environment variables on Windows when
+    /// `restore_env` is set.
+    #[cfg_attr(not(windows), allow(unused_variables))]
+    pub async fn uninstall(toolchain_path: &Path, restore_env: bool) -> Result<(), Error> {
         info!("Uninstalling Xtensa LLVM");
         let llvm_path = toolchain_path.join(CLANG_NAME);
         if llvm_path.exists() {
             #[cfg(windows)]
-            if cfg!(windows) {
+            if restore_env {
                 let mut updated_path = get_windows_path_var()?.replace(
                     &format!(
                         "{}\\{}\\esp-clang\\bin;",
@@ -264,14 +331,13 @@ impl Llvm {
             remove_dir_all(&llvm_path)
                 .await
                 .map_err(|_| Error::RemoveDirectory(llvm_path.display().to_string()))?;
+            // Only remove the symlink, `~/.espup` also holds the configs and artifacts of other
+            // installations.
             #[cfg(unix)]
             if cfg!(unix) {
-                let espup_dir = BaseDirs::new().unwrap().home_dir().join(".espup");
-
-                if espup_dir.exists() {
-                    remove_dir_all(espup_dir.display().to_string())
-                        .await
-                        .map_err(|_| Error::RemoveDirectory(espup_dir.display().to_string()))?;
+                let llvm_symlink_path = get_espup_dir()?.join("esp-clang");
+                if llvm_symlink_path.is_symlink() {
+                    std::fs::remove_file(&llvm_symlink_path)?;
                 }
             }
         }
@@ -282,8 +348,6 @@ impl Llvm {
 #[async_trait]
 impl Installable for Llvm {
     async fn install(&self) -> Result<Vec<String>, Error> {
-        let mut exports: Vec<String> = Vec::new();
-
         #[cfg(unix)]
         let install_path = if self.extended {
             Path::new(&self.path).join("esp-clang").join("include")
@@ -297,6 +361,13 @@ impl Installable for Llvm {
             self.path.join(&self.version)
         };
 
+        if install_path.exists() && self.force {
+            info!(
+                "Removing previous installation of LLVM in: '{}'",
+                self.path.display()
+            );
+            remove_dir_all(&self.path).await?;
+        }
         if install_path.exists() {
             warn!(
                 "Previous installation of LLVM exists in: '{}'. Reusing this installation",
@@ -311,6 +382,7 @@ impl Installable for Llvm {
                     self.path.to_str().unwrap(),
                     true,
                     false,
+                    !self.no_verify,
                 )
                 .await?;
             }
@@ -321,6 +393,7 @@ impl Installable for Llvm {
                     self.path.to_str().unwrap(),
                     true,
                     false,
+                    !self.no_verify,
                 )
                 .await?;
             }
@@ -330,18 +403,12 @@ impl Installable for Llvm {
         if cfg!(windows) {
             File::create(self.path.join(&self.version))?;
             let libclang_dll = format!("{}\\libclang.dll", self.get_lib_path());
-            exports.push(format!("$Env:LIBCLANG_PATH = \"{}\"", libclang_dll));
-            exports.push(format!(
-                "$Env:PATH = \"{};\" + $Env:PATH",
-                self.get_lib_path()
-            ));
             env::set_var("LIBCLANG_BIN_PATH", self.get_lib_path());
             env::set_var("LIBCLANG_PATH", libclang_dll);
         }
         #[cfg(unix)]
         if cfg!(unix) {
-            exports.push(format!("export LIBCLANG_PATH=\"{}\"", self.get_lib_path()));
-            let espup_dir = BaseDirs::new().unwrap().home_dir().join(".espup");
+            let espup_dir = get_espup_dir()?;
 
             if !espup_dir.exists() {
                 create_dir_all(espup_dir.display().to_string())
@@ -361,20 +428,61 @@ impl Installable for Llvm {
             symlink(self.get_lib_path(), llvm_symlink_path)?;
         }
 
+        #[cfg(windows)]
         if self.extended {
-            #[cfg(windows)]
-            if cfg!(windows) {
+            env::set_var("CLANG_PATH", self.get_bin_path());
+        }
+
+        Ok(self.exports())
+    }
+
+    fn exports(&self) -> Vec<String> {
+        let mut exports: Vec<String> = Vec::new();
+        #[cfg(windows)]
+        {
+            let libclang_dll = format!("{}\\libclang.dll", self.get_lib_path());
+            exports.push(format!("$Env:LIBCLANG_PATH = \"{}\"", libclang_dll));
+            exports.push(format!(
+                "$Env:PATH = \"{};\" + $Env:PATH",
+                self.get_lib_path()
+            ));
+            if self.extended {
                 exports.push(format!("$Env:CLANG_PATH = \"{}\"", self.get_bin_path()));
-                env::set_var("CLANG_PATH", self.get_bin_path());
             }
-            #[cfg(unix)]
-            exports.push(format!("export CLANG_PATH=\"{}\"", self.get_bin_path()));
         }
-
-        Ok(exports)
+        #[cfg(unix)]
+        {
+            exports.push(format!("export LIBCLANG_PATH=\"{}\"", self.get_lib_path()));
+            if self.extended {
+                exports.push(format!("export CLANG_PATH=\"{}\"", self.get_bin_path()));
+            }
+        }
+        exports
     }
 
     fn name(&self) -> String {
         "LLVM".to_string()
     }
+
+    fn license(&self) -> Option<&'static str> {
+        Some("Apache-2.0 WITH LLVM-exception")
+    }
+
+    fn artifacts(&self) -> Vec<String> {
+        [&self.file_name_libs, &self.file_name_full]
+            .into_iter()
+            .flatten()
+            .map(|file_name| format!("{}/{}", self.repository_url, file_name))
+            .collect()
+    }
+
+    fn installed_size(&self) -> u64 {
+        if self.path.exists() && !self.force {
+            0
+        } else if self.extended {
+            LLVM_EXTENDED_INSTALLED_SIZE
+        } else {
+            LLVM_INSTALLED_SIZE
+        }
+    }
 }
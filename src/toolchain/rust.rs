@@ -1,14 +1,16 @@
 //! Xtensa Rust Toolchain source and installation tools.
 
 use crate::{
+    cli::Linker,
+    config::get_config_dir,
     error::Error,
     host_triple::HostTriple,
     toolchain::{
         download_file,
         gcc::{RISCV_GCC, XTENSA_GCC},
-        github_query,
+        get_component_fingerprint, github_query,
         llvm::CLANG_NAME,
-        Installable,
+        select_release_asset, Installable, FINGERPRINT_FILE,
     },
 };
 use async_trait::async_trait;
@@ -16,15 +18,17 @@ use directories::BaseDirs;
 use log::{debug, info, warn};
 use miette::Result;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 #[cfg(unix)]
 use std::fs::create_dir_all;
 use std::{
     env,
     fmt::Debug,
-    fs::read_dir,
+    fs::{self, read_dir},
     io,
     path::{Path, PathBuf},
     process::{Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 #[cfg(unix)]
 use tempfile::tempdir_in;
@@ -39,12 +43,48 @@ const XTENSA_RUST_LATEST_API_URL: &str =
     "https://api.github.com/repos/esp-rs/rust-build/releases/latest";
 const XTENSA_RUST_API_URL: &str =
     "https://api.github.com/repos/esp-rs/rust-build/releases?page=1&per_page=100";
+/// Approximate disk space of the Xtensa Rust toolchain, including `rust-src`.
+const XTENSA_RUST_INSTALLED_SIZE: u64 = 900 * 1024 * 1024;
+
+/// File of `~/.espup` caching the latest Xtensa Rust version.
+const LATEST_VERSION_CACHE_FILE: &str = "latest-version.json";
 
 /// Xtensa Rust Toolchain version regex.
 pub const RE_EXTENDED_SEMANTIC_VERSION: &str = r"^(?P<major>0|[1-9]\d*)\.(?P<minor>0|[1-9]\d*)\.(?P<patch>0|[1-9]\d*)\.(?P<subpatch>0|[1-9]\d*)?$";
 const RE_SEMANTIC_VERSION: &str =
     r"^(?P<major>0|[1-9]\d*)\.(?P<minor>0|[1-9]\d*)\.(?P<patch>0|[1-9]\d*)?$";
 
+/// RISC-V Rust targets.
+pub const RISCV_TARGETS: [&str; 3] = [
+    "riscv32imc-unknown-none-elf",
+    "riscv32imac-unknown-none-elf",
+    "riscv32imafc-unknown-none-elf",
+];
+
+/// Latest Xtensa Rust version looked up from GitHub.
+#[derive(Debug, Serialize, Deserialize)]
+struct LatestVersionCache {
+    version: String,
+    /// Seconds since the Unix epoch when the version was looked up.
+    checked_at: u64,
+}
+
+impl LatestVersionCache {
+    /// Loads the cached version, if it was looked up less than `ttl` before `now`.
+    fn load(path: &Path, ttl: Duration, now: u64) -> Option<String> {
+        let cache: Self = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        (now.saturating_sub(cache.checked_at) < ttl.as_secs()).then_some(cache.version)
+    }
+
+    /// Saves the cache, creating its directory if needed.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self).unwrap())
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct XtensaRust {
     /// Path to the cargo home directory.
@@ -53,8 +93,12 @@ pub struct XtensaRust {
     pub dist_file: String,
     /// Xtensa Rust toolchain URL.
     pub dist_url: String,
+    /// Whether to remove a previous installation instead of reusing it.
+    pub force: bool,
     /// Host triple.
     pub host_triple: String,
+    /// Whether to skip the verification of the downloaded artifacts.
+    pub no_verify: bool,
     /// LLVM Toolchain path.
     pub path: PathBuf,
     /// Path to the rustup home directory.
@@ -88,8 +132,92 @@ impl XtensaRust {
         Ok(version)
     }
 
+    /// Get the latest version of Xtensa Rust toolchain, reusing the one cached in `~/.espup` if
+    /// it was looked up less than `ttl` ago and `refresh` isn't set. A looked up version is only
+    /// cached when `save` is set.
+    pub async fn get_cached_latest_version(
+        ttl: Duration,
+        refresh: bool,
+        save: bool,
+    ) -> Result<String> {
+        let cache_file = get_espup_dir()?.join(LATEST_VERSION_CACHE_FILE);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if !refresh {
+            if let Some(version) = LatestVersionCache::load(&cache_file, ttl, now) {
+                debug!("Using cached latest Xtensa Rust version: {}", version);
+                return Ok(version);
+            }
+        }
+        let version = Self::get_latest_version().await?;
+        if !save {
+            return Ok(version);
+        }
+        let cache = LatestVersionCache {
+            version: version.clone(),
+            checked_at: now,
+        };
+        if let Err(e) = cache.save(&cache_file) {
+            warn!(
+                "Failed to cache the latest Xtensa Rust version in '{}': {}",
+                cache_file.display(),
+                e
+            );
+        }
+        Ok(version)
+    }
+
+    /// Gets the version reported by `rustc` of an installed toolchain.
+    pub fn get_installed_version(toolchain_name: &str) -> Option<String> {
+        let rustc_version = Command::new("rustc")
+            .args([&format!("+{toolchain_name}"), "--version"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        rustc_version.status.success().then(|| {
+            String::from_utf8_lossy(&rustc_version.stdout)
+                .trim()
+                .to_string()
+        })
+    }
+
+    /// Returns the version of a rustup toolchain with the same name that differs from the version
+    /// to install, if any.
+    pub fn get_conflicting_version(&self) -> Option<String> {
+        let toolchain_name = self.toolchain_destination.file_name()?.to_str()?;
+        let toolchains = Command::new("rustup")
+            .args(["toolchain", "list"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !lists_toolchain(&String::from_utf8_lossy(&toolchains.stdout), toolchain_name) {
+            return None;
+        }
+        let installed_version = Self::get_installed_version(toolchain_name)?;
+        (!installed_version.contains(&self.version)).then_some(installed_version)
+    }
+
+    /// Returns whether the installed files still match the fingerprint saved when installing,
+    /// installations without a fingerprint are assumed to be unmodified.
+    fn is_unmodified(&self) -> Result<bool, Error> {
+        let Ok(fingerprint) = fs::read_to_string(self.toolchain_destination.join(FINGERPRINT_FILE))
+        else {
+            return Ok(true);
+        };
+        Ok(fingerprint.trim()
+            == get_component_fingerprint(&self.toolchain_destination, &self.name())?)
+    }
+
     /// Create a new instance.
-    pub fn new(toolchain_version: &str, host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+    pub fn new(
+        toolchain_version: &str,
+        host_triple: &HostTriple,
+        toolchain_path: &Path,
+    ) -> Result<Self, Error> {
         let artifact_extension = get_artifact_extension(host_triple);
         let version = toolchain_version.to_string();
         let dist = format!("rust-{version}-{host_triple}");
@@ -101,15 +229,17 @@ impl XtensaRust {
         let src_dist_file = format!("{src_dist}.{artifact_extension}");
         #[cfg(unix)]
         let src_dist_url = format!("{DEFAULT_XTENSA_RUST_REPOSITORY}/v{version}/{src_dist_file}");
-        let cargo_home = get_cargo_home();
-        let rustup_home = get_rustup_home();
+        let cargo_home = get_cargo_home()?;
+        let rustup_home = get_rustup_home()?;
         let toolchain_destination = toolchain_path.to_path_buf();
 
-        Self {
+        Ok(Self {
             cargo_home,
             dist_file,
             dist_url,
+            force: false,
             host_triple: host_triple.to_string(),
+            no_verify: false,
             path: toolchain_path.to_path_buf(),
             rustup_home,
             #[cfg(unix)]
@@ -118,7 +248,31 @@ impl XtensaRust {
             src_dist_url,
             toolchain_destination,
             version,
+        })
+    }
+
+    /// Uses the host artifacts of a GitHub release instead of the default ones.
+    pub fn use_release_assets(
+        &mut self,
+        assets: &[String],
+        release_url: &str,
+    ) -> Result<(), Error> {
+        let host_triple = self.host_triple.clone();
+        self.dist_url = select_release_asset(assets, "Xtensa Rust", release_url, |name| {
+            name.starts_with("rust-")
+                && !name.starts_with("rust-src")
+                && name.contains(&host_triple)
+        })?;
+        self.dist_file = self.dist_url.rsplit('/').next().unwrap().to_string();
+        #[cfg(unix)]
+        {
+            self.src_dist_url =
+                select_release_asset(assets, "Xtensa Rust source", release_url, |name| {
+                    name.starts_with("rust-src")
+                })?;
+            self.src_dist_file = self.src_dist_url.rsplit('/').next().unwrap().to_string();
         }
+        Ok(())
     }
 
     /// Parses the version of the Xtensa toolchain.
@@ -192,7 +346,13 @@ impl XtensaRust {
 #[async_trait]
 impl Installable for XtensaRust {
     async fn install(&self) -> Result<Vec<String>, Error> {
-        if self.toolchain_destination.exists() {
+        if self.toolchain_destination.exists() && self.force {
+            info!(
+                "Removing previous installation of Xtensa Rust in: '{}'",
+                self.toolchain_destination.display()
+            );
+            Self::uninstall(&self.toolchain_destination).await?;
+        } else if self.toolchain_destination.exists() {
             let toolchain_name = format!(
                 "+{}",
                 self.toolchain_destination
@@ -206,7 +366,8 @@ impl Installable for XtensaRust {
                 .stdout(Stdio::piped())
                 .output()?;
             let output = String::from_utf8_lossy(&rustc_version.stdout);
-            if rustc_version.status.success() && output.contains(&self.version) {
+            let same_version = rustc_version.status.success() && output.contains(&self.version);
+            if same_version && self.is_unmodified()? {
                 warn!(
                 "Previous installation of Xtensa Rust {} exists in: '{}'. Reusing this installation",
                 &self.version,
@@ -214,7 +375,9 @@ impl Installable for XtensaRust {
             );
                 return Ok(vec![]);
             } else {
-                if !rustc_version.status.success() {
+                if same_version {
+                    warn!("Xtensa Rust was modified since it was installed, reinstalling it");
+                } else if !rustc_version.status.success() {
                     warn!("Failed to detect version of Xtensa Rust, reinstalling it");
                 }
                 Self::uninstall(&self.toolchain_destination).await?;
@@ -225,7 +388,7 @@ impl Installable for XtensaRust {
 
         #[cfg(unix)]
         if cfg!(unix) {
-            let path = get_rustup_home().join("tmp");
+            let path = self.rustup_home.join("tmp");
             if !path.exists() {
                 info!("Creating directory: '{}'", path.display());
                 create_dir_all(&path)
@@ -240,6 +403,7 @@ impl Installable for XtensaRust {
                 tmp_dir_path,
                 true,
                 false,
+                !self.no_verify,
             )
             .await?;
 
@@ -249,6 +413,7 @@ impl Installable for XtensaRust {
                 tmp_dir_path,
                 true,
                 false,
+                !self.no_verify,
             )
             .await?;
 
@@ -305,46 +470,142 @@ impl Installable for XtensaRust {
                 &self.toolchain_destination.display().to_string(),
                 true,
                 true,
+                !self.no_verify,
             )
             .await?;
         }
 
+        let fingerprint = get_component_fingerprint(&self.toolchain_destination, &self.name())?;
+        fs::write(
+            self.toolchain_destination.join(FINGERPRINT_FILE),
+            fingerprint,
+        )?;
+
         Ok(vec![]) // No exports
     }
 
     fn name(&self) -> String {
         "Xtensa Rust".to_string()
     }
+
+    fn license(&self) -> Option<&'static str> {
+        Some("MIT OR Apache-2.0")
+    }
+
+    fn artifacts(&self) -> Vec<String> {
+        #[cfg(unix)]
+        let artifacts = vec![self.src_dist_url.clone(), self.dist_url.clone()];
+        #[cfg(windows)]
+        let artifacts = vec![self.dist_url.clone()];
+        artifacts
+    }
+
+    fn installed_size(&self) -> u64 {
+        if self.toolchain_destination.exists() && !self.force {
+            0
+        } else {
+            XTENSA_RUST_INSTALLED_SIZE
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct RiscVTarget {
+    /// Linker used for the RISC-V targets.
+    pub linker: Linker,
     /// Nightly version.
     pub nightly_version: String,
+    /// Whether to also add the targets to the stable toolchain.
+    pub on_stable: bool,
+    /// Whether to update the nightly toolchain before installing the targets.
+    pub rustup_update: bool,
 }
 
 impl RiscVTarget {
     /// Create a crate instance.
-    pub fn new(nightly_version: &str) -> Self {
+    pub fn new(
+        nightly_version: &str,
+        linker: Linker,
+        rustup_update: bool,
+        on_stable: bool,
+    ) -> Self {
         RiscVTarget {
+            linker,
             nightly_version: nightly_version.to_string(),
+            on_stable,
+            rustup_update,
         }
     }
 
-    /// Uninstalls the RISC-V target.
-    pub fn uninstall(nightly_version: &str) -> Result<(), Error> {
-        info!("Uninstalling RISC-V target");
+    /// Adds the RISC-V targets to the stable toolchain.
+    fn install_on_stable() -> Result<(), Error> {
+        info!("Adding RISC-V Rust targets to 'stable' toolchain");
+        if !Command::new("rustup")
+            .args(["target", "add", "--toolchain", "stable"])
+            .args(RISCV_TARGETS)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?
+            .success()
+        {
+            return Err(Error::InstallRiscvTarget("stable".to_string()));
+        }
+        Ok(())
+    }
 
+    /// Updates the nightly toolchain with rustup and reports its resulting version.
+    fn update_toolchain(&self) -> Result<(), Error> {
+        info!("Updating '{}' toolchain", &self.nightly_version);
         if !Command::new("rustup")
-            .args([
-                "target",
-                "remove",
-                "--toolchain",
-                nightly_version,
-                "riscv32imc-unknown-none-elf",
-                "riscv32imac-unknown-none-elf",
-                "riscv32imafc-unknown-none-elf",
-            ])
+            .args(["update", &self.nightly_version])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?
+            .success()
+        {
+            return Err(Error::RustupUpdate(self.nightly_version.clone()));
+        }
+
+        let rustc_version = Command::new("rustc")
+            .args([&format!("+{}", self.nightly_version), "--version"])
+            .stdout(Stdio::piped())
+            .output()?;
+        info!(
+            "Toolchain '{}' updated to: {}",
+            &self.nightly_version,
+            String::from_utf8_lossy(&rustc_version.stdout).trim()
+        );
+        Ok(())
+    }
+
+    /// Returns the exports that configure Cargo to use `rust-lld` for the RISC-V targets.
+    fn lld_exports() -> Vec<String> {
+        RISCV_TARGETS
+            .iter()
+            .map(|target| {
+                let var = format!(
+                    "CARGO_TARGET_{}_LINKER",
+                    target.to_uppercase().replace('-', "_")
+                );
+                #[cfg(windows)]
+                let export = format!("$Env:{var} = \"rust-lld\"");
+                #[cfg(unix)]
+                let export = format!("export {var}=\"rust-lld\"");
+                export
+            })
+            .collect()
+    }
+
+    /// Removes the RISC-V targets from `toolchain`.
+    pub fn uninstall(toolchain: &str) -> Result<(), Error> {
+        info!(
+            "Removing RISC-V Rust targets from '{}' toolchain",
+            toolchain
+        );
+
+        if !Command::new("rustup")
+            .args(["target", "remove", "--toolchain", toolchain])
+            .args(RISCV_TARGETS)
             .stdout(Stdio::null())
             .status()?
             .success()
@@ -362,6 +623,10 @@ impl Installable for RiscVTarget {
             "Installing RISC-V Rust targets ('riscv32imc-unknown-none-elf', 'riscv32imac-unknown-none-elf' and 'riscv32imafc-unknown-none-elf') for '{}' toolchain",            &self.nightly_version
         );
 
+        if self.rustup_update {
+            self.update_toolchain()?;
+        }
+
         if !Command::new("rustup")
             .args([
                 "toolchain",
@@ -383,8 +648,20 @@ impl Installable for RiscVTarget {
         {
             return Err(Error::InstallRiscvTarget(self.nightly_version.clone()));
         }
+        ensure_rust_src(&self.nightly_version)?;
 
-        Ok(vec![]) // No exports
+        if self.on_stable {
+            Self::install_on_stable()?;
+        }
+
+        Ok(self.exports())
+    }
+
+    fn exports(&self) -> Vec<String> {
+        match self.linker {
+            Linker::Gcc => vec![], // No exports
+            Linker::Lld => Self::lld_exports(),
+        }
     }
 
     fn name(&self) -> String {
@@ -401,27 +678,141 @@ fn get_artifact_extension(host_triple: &HostTriple) -> &str {
 }
 
 /// Gets the default cargo home path.
-fn get_cargo_home() -> PathBuf {
-    PathBuf::from(env::var("CARGO_HOME").unwrap_or_else(|_e| {
-        format!(
-            "{}",
-            BaseDirs::new().unwrap().home_dir().join(".cargo").display()
-        )
-    }))
+pub fn get_cargo_home() -> Result<PathBuf, Error> {
+    match env::var_os("CARGO_HOME") {
+        Some(cargo_home) => Ok(PathBuf::from(cargo_home)),
+        None => Ok(BaseDirs::new()
+            .ok_or(Error::NoHomeDir)?
+            .home_dir()
+            .join(".cargo")),
+    }
+}
+
+/// Gets the path of the directory where espup keeps its own files, `~/.espup`, or the config
+/// directory when there is no home directory.
+pub fn get_espup_dir() -> Result<PathBuf, Error> {
+    match BaseDirs::new() {
+        Some(dirs) => Ok(dirs.home_dir().join(".espup")),
+        None => get_config_dir(),
+    }
+}
+
+/// Gets the directory of a toolchain in the rustup home, the link of the `--install-path` ones.
+pub fn get_rustup_toolchain_dir(toolchain: &str) -> Result<PathBuf, Error> {
+    Ok(get_rustup_home()?.join("toolchains").join(toolchain))
 }
 
 /// Gets the default rustup home path.
-pub fn get_rustup_home() -> PathBuf {
-    PathBuf::from(env::var("RUSTUP_HOME").unwrap_or_else(|_e| {
-        format!(
-            "{}",
-            BaseDirs::new()
-                .unwrap()
-                .home_dir()
-                .join(".rustup")
-                .display()
-        )
-    }))
+pub fn get_rustup_home() -> Result<PathBuf, Error> {
+    match env::var_os("RUSTUP_HOME") {
+        Some(rustup_home) => Ok(PathBuf::from(rustup_home)),
+        None => Ok(BaseDirs::new()
+            .ok_or(Error::NoHomeDir)?
+            .home_dir()
+            .join(".rustup")),
+    }
+}
+
+/// Returns whether the output of `rustup toolchain list` contains a toolchain.
+fn lists_toolchain(toolchains: &str, toolchain_name: &str) -> bool {
+    toolchains
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(toolchain_name))
+}
+
+/// Gets the name of the default rustup toolchain, if any.
+pub fn get_default_toolchain() -> Option<String> {
+    let output = Command::new("rustup")
+        .arg("default")
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+}
+
+/// Sets the default rustup toolchain.
+pub fn set_default_toolchain(toolchain: &str) -> Result<(), Error> {
+    info!("Setting '{}' as the default toolchain", toolchain);
+    if !Command::new("rustup")
+        .args(["default", toolchain])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?
+        .success()
+    {
+        return Err(Error::SetDefaultToolchain(toolchain.to_string()));
+    }
+    Ok(())
+}
+
+/// Links a toolchain installed outside of the rustup home, so rustup finds it by its name.
+pub fn link_toolchain(toolchain: &str, toolchain_dir: &Path) -> Result<(), Error> {
+    let toolchain_link = get_rustup_toolchain_dir(toolchain)?;
+    if toolchain_link.exists() && !toolchain_link.is_symlink() {
+        warn!(
+            "Toolchain '{}' is also installed in '{}', rustup will use it instead of '{}'",
+            toolchain,
+            toolchain_link.display(),
+            toolchain_dir.display()
+        );
+        return Ok(());
+    }
+    debug!(
+        "Linking '{}' toolchain to '{}'",
+        toolchain,
+        toolchain_dir.display()
+    );
+    if !Command::new("rustup")
+        .args(["toolchain", "link", toolchain])
+        .arg(toolchain_dir)
+        .stdout(Stdio::null())
+        .status()?
+        .success()
+    {
+        return Err(Error::LinkToolchain(toolchain.to_string()));
+    }
+    Ok(())
+}
+
+/// Removes the rustup toolchain entry if it's a link pointing to a directory that no longer exists.
+///
+/// Returns `true` if a dangling link was found and removed.
+pub fn remove_dangling_toolchain_link(toolchain_path: &Path) -> Result<bool, Error> {
+    let is_dangling = toolchain_path
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+        && !toolchain_path.exists();
+    if !is_dangling {
+        return Ok(false);
+    }
+
+    warn!(
+        "Toolchain link '{}' points to a directory that no longer exists, removing it",
+        toolchain_path.display()
+    );
+    remove_toolchain_link(toolchain_path)
+}
+
+/// Removes the rustup toolchain entry if it's a link, leaving the directory it points to.
+///
+/// Returns `true` if a link was found and removed.
+pub fn remove_toolchain_link(toolchain_path: &Path) -> Result<bool, Error> {
+    if !toolchain_path
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+    {
+        return Ok(false);
+    }
+    debug!("Removing toolchain link '{}'", toolchain_path.display());
+    // Directory symlinks on Windows have to be removed as directories
+    fs::remove_file(toolchain_path)
+        .or_else(|_| fs::remove_dir(toolchain_path))
+        .map_err(|_| Error::DanglingToolchainLink(toolchain_path.display().to_string()))?;
+    Ok(true)
 }
 
 /// Checks if rustup is installed.
@@ -443,19 +834,53 @@ pub async fn check_rust_installation() -> Result<(), Error> {
     Ok(())
 }
 
+/// Makes sure that a toolchain has the `rust-src` component required by `build-std`, adding it
+/// if it's missing.
+pub fn ensure_rust_src(toolchain: &str) -> Result<(), Error> {
+    let installed = Command::new("rustup")
+        .args(["component", "list", "--installed", "--toolchain", toolchain])
+        .stderr(Stdio::null())
+        .output()?;
+    if String::from_utf8_lossy(&installed.stdout)
+        .lines()
+        .any(|line| line.trim() == "rust-src")
+    {
+        return Ok(());
+    }
+
+    info!(
+        "Adding missing 'rust-src' component to '{}' toolchain",
+        toolchain
+    );
+    if !Command::new("rustup")
+        .args(["component", "add", "rust-src", "--toolchain", toolchain])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?
+        .success()
+    {
+        return Err(Error::MissingRustSrc(toolchain.to_string()));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(unix)]
+    use crate::toolchain::rust::{remove_dangling_toolchain_link, RiscVTarget};
     use crate::{
         logging::initialize_logger,
-        toolchain::rust::{get_cargo_home, get_rustup_home, XtensaRust},
+        toolchain::rust::{
+            get_cargo_home, get_rustup_home, lists_toolchain, LatestVersionCache, XtensaRust,
+        },
     };
     use directories::BaseDirs;
-    use std::env;
+    use std::{env, time::Duration};
     use tempfile::TempDir;
 
     #[test]
     fn test_xtensa_rust_parse_version() {
-        initialize_logger("debug");
+        initialize_logger("debug", None, None);
         assert_eq!(XtensaRust::parse_version("1.65.0.0").unwrap(), "1.65.0.0");
         assert_eq!(XtensaRust::parse_version("1.65.0.1").unwrap(), "1.65.0.1");
         assert_eq!(XtensaRust::parse_version("1.64.0.0").unwrap(), "1.64.0.0");
@@ -470,19 +895,88 @@ mod tests {
         assert!(XtensaRust::parse_version("1._.*.1").is_err());
     }
 
+    #[test]
+    fn test_latest_version_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_file = temp_dir.path().join("espup/latest-version.json");
+        let ttl = Duration::from_secs(24 * 60 * 60);
+        assert!(LatestVersionCache::load(&cache_file, ttl, 1000).is_none());
+
+        LatestVersionCache {
+            version: "1.82.0.3".to_string(),
+            checked_at: 1000,
+        }
+        .save(&cache_file)
+        .unwrap();
+        assert_eq!(
+            LatestVersionCache::load(&cache_file, ttl, 1000 + 60).as_deref(),
+            Some("1.82.0.3")
+        );
+        assert!(LatestVersionCache::load(&cache_file, ttl, 1000 + ttl.as_secs()).is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_riscv_lld_exports() {
+        assert_eq!(
+            RiscVTarget::lld_exports(),
+            [
+                "export CARGO_TARGET_RISCV32IMC_UNKNOWN_NONE_ELF_LINKER=\"rust-lld\"",
+                "export CARGO_TARGET_RISCV32IMAC_UNKNOWN_NONE_ELF_LINKER=\"rust-lld\"",
+                "export CARGO_TARGET_RISCV32IMAFC_UNKNOWN_NONE_ELF_LINKER=\"rust-lld\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lists_toolchain() {
+        let toolchains = "stable-x86_64-unknown-linux-gnu (default)\nesp\nesp-old\n";
+        assert!(lists_toolchain(toolchains, "esp"));
+        assert!(lists_toolchain(
+            toolchains,
+            "stable-x86_64-unknown-linux-gnu"
+        ));
+        assert!(!lists_toolchain(toolchains, "nightly"));
+        assert!(!lists_toolchain("no installed toolchains\n", "esp"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_remove_dangling_toolchain_link() {
+        use std::{fs::create_dir, os::unix::fs::symlink};
+
+        let temp_dir = TempDir::new().unwrap();
+        let target_dir = temp_dir.path().join("xtensa-rust");
+        let toolchain_link = temp_dir.path().join("esp");
+        create_dir(&target_dir).unwrap();
+        symlink(&target_dir, &toolchain_link).unwrap();
+
+        // Valid link is kept
+        assert!(!remove_dangling_toolchain_link(&toolchain_link).unwrap());
+        assert!(toolchain_link.exists());
+
+        // Dangling link is removed
+        std::fs::remove_dir(&target_dir).unwrap();
+        assert!(remove_dangling_toolchain_link(&toolchain_link).unwrap());
+        assert!(toolchain_link.symlink_metadata().is_err());
+
+        // Missing toolchain is not an error
+        assert!(!remove_dangling_toolchain_link(&toolchain_link).unwrap());
+    }
+
     #[test]
     fn test_get_cargo_home() {
         // No CARGO_HOME set
         env::remove_var("CARGO_HOME");
         assert_eq!(
-            get_cargo_home(),
+            get_cargo_home().unwrap(),
             BaseDirs::new().unwrap().home_dir().join(".cargo")
         );
         // CARGO_HOME set
         let temp_dir = TempDir::new().unwrap();
         let cargo_home = temp_dir.path().to_path_buf();
         env::set_var("CARGO_HOME", cargo_home.to_str().unwrap());
-        assert_eq!(get_cargo_home(), cargo_home);
+        assert_eq!(get_cargo_home().unwrap(), cargo_home);
     }
 
     #[test]
@@ -490,13 +984,13 @@ mod tests {
         // No RUSTUP_HOME set
         env::remove_var("RUSTUP_HOME");
         assert_eq!(
-            get_rustup_home(),
+            get_rustup_home().unwrap(),
             BaseDirs::new().unwrap().home_dir().join(".rustup")
         );
         // RUSTUP_HOME set
         let temp_dir = TempDir::new().unwrap();
         let rustup_home = temp_dir.path().to_path_buf();
         env::set_var("RUSTUP_HOME", rustup_home.to_str().unwrap());
-        assert_eq!(get_rustup_home(), rustup_home);
+        assert_eq!(get_rustup_home().unwrap(), rustup_home);
     }
 }
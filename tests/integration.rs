@@ -41,3 +41,146 @@ fn verify_uninstall_help() {
         .assert()
         .success();
 }
+
+#[test]
+fn verify_info_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["info", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_completions() {
+    for shell in ["bash", "elvish", "fish", "powershell", "zsh"] {
+        let output = assert_cmd::Command::cargo_bin("espup")
+            .unwrap()
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        assert!(String::from_utf8_lossy(&output).contains("espup"));
+    }
+}
+
+#[test]
+fn verify_backup_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["backup", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_list_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["list", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_restore_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["restore", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn fails_to_uninstall_missing_toolchain() {
+    let rustup_home = tempfile::TempDir::new().unwrap();
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .env("RUSTUP_HOME", rustup_home.path())
+        .args(["uninstall", "--name", "missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn verify_reinstall_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["reinstall", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn fails_to_reinstall_missing_toolchain() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["reinstall", "--name", "missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn verify_doctor_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["doctor", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_download_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["download", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn fails_to_install_offline_without_bundle() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["install", "--offline", "--toolchain-version", "1.82.0.3"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn verify_export_only_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["export-only", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_add_target_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["add-target", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_remove_target_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["remove-target", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_show_config_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["show-config", "--help"])
+        .assert()
+        .success();
+}